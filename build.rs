@@ -0,0 +1,51 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Downsampled dust map produced by dust_map.py
+const DUST_MAP_FILE: &str = "dust_map.txt";
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|s| s.trim().to_owned())
+}
+
+fn main() {
+    // Docker builds have no .git, so the commit can be given by GIT_COMMIT build argument
+    let git_commit = env::var("GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| command_output("git", &["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_owned());
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let rustc_version =
+        command_output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_owned());
+
+    println!("cargo:rustc-env=GIT_COMMIT={}", git_commit);
+    println!("cargo:rustc-env=RUSTC_VERSION={}", rustc_version);
+    println!("cargo:rustc-env=TARGET={}", env::var("TARGET").unwrap());
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+
+    // The map is embedded into the binary, an empty one means there is no bundled map
+    let dust_map = fs::read_to_string(DUST_MAP_FILE).unwrap_or_else(|_| {
+        println!(
+            "cargo:warning={} is missing, generate it with dust_map.py to bundle the dust map",
+            DUST_MAP_FILE
+        );
+        String::new()
+    });
+    fs::write(
+        Path::new(&env::var("OUT_DIR").unwrap()).join(DUST_MAP_FILE),
+        dust_map,
+    )
+    .unwrap();
+    println!("cargo:rerun-if-changed={}", DUST_MAP_FILE);
+}
@@ -0,0 +1,47 @@
+#!/usr/bin/env python3
+"""Downsampled SFD dust map bundled into the binary
+
+Writes dust_map.txt: numbers of Galactic longitude and latitude nodes followed by E(B-V) values
+in rows of constant latitude from -90 to 90 degrees, longitudes of a row go from 0 to 360
+degrees exclusive. The file is embedded at build time if it exists in the crate root, commit it
+there to bundle the map into non-Docker builds too, cargo warns if it is missing.
+
+Requires `dustmaps` package, the full-resolution map is downloaded to DUSTMAPS_DATA_DIR.
+"""
+
+import os
+import sys
+
+import astropy.units as u
+import numpy as np
+from astropy.coordinates import SkyCoord
+from dustmaps.config import config
+
+
+# Grid step, degrees
+STEP = 1.0
+
+
+def main(path='dust_map.txt'):
+    config['data_dir'] = os.environ.get('DUSTMAPS_DATA_DIR', 'dustmaps-data')
+    # Import after the configuration, so the data directory is used
+    import dustmaps.sfd
+
+    dustmaps.sfd.fetch()
+    sfd = dustmaps.sfd.SFDQuery()
+
+    l = np.arange(0.0, 360.0, STEP)
+    b = np.linspace(-90.0, 90.0, round(180.0 / STEP) + 1)
+    ll, bb = np.meshgrid(l, b)
+    coords = SkyCoord(l=ll.ravel() * u.deg, b=bb.ravel() * u.deg, frame='galactic')
+    ebv = sfd(coords).reshape(b.size, l.size)
+
+    with open(path, 'w') as fh:
+        fh.write(f'{l.size} {b.size}\n')
+        for row in ebv:
+            fh.write(' '.join(f'{x:.4f}' for x in row))
+            fh.write('\n')
+
+
+if __name__ == '__main__':
+    main(*sys.argv[1:])
@@ -0,0 +1,118 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::{json::serde_json, Deserialize, Serialize};
+use rocket::{Data, Request, Response};
+use std::io::Cursor;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Object identifier of the request, echoed in the response and written to the access log
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ObjectId {
+    Integer(i64),
+    String(String),
+}
+
+#[derive(Debug, Default)]
+struct Object {
+    id: Option<ObjectId>,
+    n_obs: Option<usize>,
+}
+
+/// Request data recorded by the route for the access log
+#[derive(Debug, Default)]
+pub struct RequestLog {
+    object: Mutex<Object>,
+}
+
+impl RequestLog {
+    /// Record the object identifier and the light-curve length
+    pub fn object(&self, id: &Option<ObjectId>, n_obs: usize) {
+        let mut object = self.object.lock().unwrap();
+        object.id = id.clone();
+        object.n_obs = Some(n_obs);
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r RequestLog {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(req.local_cache(RequestLog::default))
+    }
+}
+
+/// Start of the request processing
+struct Started(Instant);
+
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Unix time of the response, in seconds
+    timestamp: f64,
+    method: &'a str,
+    path: &'a str,
+    /// Route of the request, e.g. `/api/v0.5/fit/<model>`
+    route: Option<String>,
+    status: u16,
+    id: Option<ObjectId>,
+    n_obs: Option<usize>,
+    /// Wall-clock time from the request to the response, in milliseconds
+    time_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Access log of JSON lines written to stdout
+///
+/// Error messages are taken from plain-text error responses, they name the failing feature if
+/// feature evaluation fails
+pub struct AccessLog;
+
+#[rocket::async_trait]
+impl Fairing for AccessLog {
+    fn info(&self) -> Info {
+        Info {
+            name: "JSON access log",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        req.local_cache(|| Started(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Started(started) = req.local_cache(|| Started(Instant::now()));
+        let time_ms = 1e3 * started.elapsed().as_secs_f64();
+
+        let status = res.status();
+        let error = if status.code < 400 {
+            None
+        } else if res.content_type().is_some_and(|ct| ct.is_plain()) {
+            let body = res.body_mut().to_string().await.unwrap_or_default();
+            res.set_sized_body(body.len(), Cursor::new(body.clone()));
+            Some(body)
+        } else {
+            status.reason().map(String::from)
+        };
+
+        let object = req.local_cache(RequestLog::default).object.lock().unwrap();
+        let record = Record {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(f64::NAN),
+            method: req.method().as_str(),
+            path: req.uri().path().as_str(),
+            route: req.route().map(|route| route.uri.to_string()),
+            status: status.code,
+            id: object.id.clone(),
+            n_obs: object.n_obs,
+            time_ms,
+            error,
+        };
+        println!("{}", serde_json::to_string(&record).unwrap());
+    }
+}
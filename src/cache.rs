@@ -0,0 +1,330 @@
+use lru::LruCache;
+use rocket::fairing::AdHoc;
+use rocket::http::Header;
+use rocket::serde::{json::serde_json, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Rocket configuration key of the cache, e.g. `ROCKET_CACHE={size=10000,ttl=86400}`
+pub const CACHE_KEY: &str = "cache";
+
+/// The persistence file is compacted when it has this times more lines than there are entries
+const COMPACTION_FACTOR: usize = 4;
+
+type FeatureValues = HashMap<String, f64>;
+
+/// Result cache configuration, the cache is disabled by default
+#[derive(Debug, Default, Deserialize)]
+pub struct CacheConfig {
+    /// Maximum number of entries, zero disables the cache
+    #[serde(default)]
+    size: usize,
+    /// Entry lifetime in seconds, unlimited by default
+    #[serde(default)]
+    ttl: Option<u64>,
+    /// File to persist the entries to
+    #[serde(default)]
+    path: Option<PathBuf>,
+}
+
+/// Persisted entry, the key is hex-encoded, non-finite values are stored as nulls and restored as
+/// NaN
+#[derive(Serialize, Deserialize)]
+struct Record {
+    key: String,
+    expires_at: Option<u64>,
+    features: HashMap<String, Option<f64>>,
+}
+
+struct Entry {
+    features: FeatureValues,
+    /// Unix time in seconds
+    expires_at: Option<u64>,
+}
+
+impl Entry {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= now())
+    }
+
+    fn record(&self, key: &[u8]) -> Record {
+        Record {
+            key: key.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            expires_at: self.expires_at,
+            features: self
+                .features
+                .iter()
+                .map(|(name, &value)| (name.clone(), Some(value).filter(|x| x.is_finite())))
+                .collect(),
+        }
+    }
+}
+
+impl From<Record> for Entry {
+    fn from(record: Record) -> Self {
+        Self {
+            features: record
+                .features
+                .into_iter()
+                .map(|(name, value)| (name, value.unwrap_or(f64::NAN)))
+                .collect(),
+            expires_at: record.expires_at,
+        }
+    }
+}
+
+/// Bytes of the hex-encoded key, `None` if it is malformed
+fn decode_key(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Cache of feature values, always managed by Rocket, so routes don't depend on the configuration
+pub enum ResultCache {
+    Disabled,
+    Enabled(Store),
+}
+
+impl ResultCache {
+    fn new(config: CacheConfig) -> Result<Self, String> {
+        match NonZeroUsize::new(config.size) {
+            Some(size) => Ok(Self::Enabled(Store::new(size, config)?)),
+            None => Ok(Self::Disabled),
+        }
+    }
+}
+
+/// In-memory LRU cache of feature values with optional persistence
+///
+/// Persisted entries are appended to a JSON-lines file, which is compacted on startup and when it
+/// has too many stale lines
+pub struct Store {
+    entries: Mutex<LruCache<Vec<u8>, Entry>>,
+    ttl: Option<u64>,
+    journal: Option<Mutex<Journal>>,
+}
+
+/// Persistence file opened for appending
+struct Journal {
+    path: PathBuf,
+    file: File,
+    /// Number of lines in the file
+    lines: usize,
+}
+
+impl Journal {
+    fn open(path: PathBuf, entries: &LruCache<Vec<u8>, Entry>) -> Result<Self, String> {
+        let (file, lines) = compact(&path, entries)?;
+        Ok(Self { path, file, lines })
+    }
+
+    fn append(&mut self, key: &[u8], entries: &LruCache<Vec<u8>, Entry>) -> Result<(), String> {
+        let entry = match entries.peek(key) {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        let mut line = serde_json::to_vec(&entry.record(key)).unwrap();
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .map_err(|e| format!("cannot write {:?}: {}", self.path, e))?;
+        self.lines += 1;
+        if self.lines > COMPACTION_FACTOR * entries.len() {
+            let (file, lines) = compact(&self.path, entries)?;
+            self.file = file;
+            self.lines = lines;
+        }
+        Ok(())
+    }
+}
+
+impl Store {
+    fn new(size: NonZeroUsize, config: CacheConfig) -> Result<Self, String> {
+        let mut entries = LruCache::new(size);
+        let journal = match config.path {
+            Some(path) => {
+                if path.exists() {
+                    load(&path, &mut entries)?;
+                }
+                Some(Mutex::new(Journal::open(path, &entries)?))
+            }
+            None => None,
+        };
+        Ok(Self {
+            entries: Mutex::new(entries),
+            ttl: config.ttl,
+            journal,
+        })
+    }
+
+    fn get(&self, key: &[u8]) -> Option<FeatureValues> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if entry.is_expired() => {
+                entries.pop(key);
+                None
+            }
+            Some(entry) => Some(entry.features.clone()),
+            None => None,
+        }
+    }
+
+    fn insert(&self, key: Vec<u8>, features: FeatureValues) {
+        let entry = Entry {
+            features,
+            expires_at: self.ttl.map(|ttl| now() + ttl),
+        };
+        let mut entries = self.entries.lock().unwrap();
+        entries.put(key.clone(), entry);
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.lock().unwrap().append(&key, &entries) {
+                warn!("Cannot persist cache entry: {}", e);
+            }
+        }
+    }
+}
+
+/// Read persisted entries, the latest record of a key wins
+fn load(path: &Path, entries: &mut LruCache<Vec<u8>, Entry>) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("cannot read {:?}: {}", path, e))?;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("cannot read {:?}: {}", path, e))?;
+        // A partially written last line is skipped
+        let record: Record = match serde_json::from_str(&line) {
+            Ok(record) => record,
+            Err(_) => continue,
+        };
+        let key = match decode_key(&record.key) {
+            Some(key) => key,
+            None => continue,
+        };
+        let entry = Entry::from(record);
+        if !entry.is_expired() {
+            entries.put(key, entry);
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite the file with the unexpired entries, least recently used first, and open it for
+/// appending, returns the file and the number of its lines
+fn compact(path: &Path, entries: &LruCache<Vec<u8>, Entry>) -> Result<(File, usize), String> {
+    let tmp_path = path.with_extension("tmp");
+    let mut text = Vec::new();
+    let mut lines = 0;
+    for (key, entry) in entries
+        .iter()
+        .rev()
+        .filter(|(_, entry)| !entry.is_expired())
+    {
+        serde_json::to_writer(&mut text, &entry.record(key)).unwrap();
+        text.push(b'\n');
+        lines += 1;
+    }
+    let file = fs::write(&tmp_path, text)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .and_then(|_| OpenOptions::new().append(true).open(path))
+        .map_err(|e| format!("cannot write {:?}: {}", path, e))?;
+    Ok((file, lines))
+}
+
+/// Cache status of the response, sent as `X-Cache` header
+#[derive(Debug, Clone, Copy)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    /// The cache is disabled or bypassed by the request
+    Bypass,
+}
+
+impl From<CacheStatus> for Header<'static> {
+    fn from(status: CacheStatus) -> Self {
+        let value = match status {
+            CacheStatus::Hit => "hit",
+            CacheStatus::Miss => "miss",
+            CacheStatus::Bypass => "bypass",
+        };
+        Header::new("X-Cache", value)
+    }
+}
+
+/// Cache key of the time series evaluated by the extractor of the route, `extractor` is its
+/// definition or the parameters defining it, `options` are the other inputs changing the result
+///
+/// The key holds all the inputs rather than their hash, so different inputs never collide
+pub fn key(
+    route: &str,
+    extractor: &str,
+    options: &str,
+    t: &[f64],
+    m: &[f64],
+    w: &[f64],
+) -> Vec<u8> {
+    let mut bytes =
+        Vec::with_capacity(route.len() + extractor.len() + options.len() + 3 + 3 * 8 * t.len());
+    for s in [route, extractor, options] {
+        bytes.extend_from_slice(s.as_bytes());
+        bytes.push(0);
+    }
+    for x in t.iter().chain(m).chain(w) {
+        bytes.extend_from_slice(&x.to_le_bytes());
+    }
+    bytes
+}
+
+/// Features from the cache if there are any, otherwise evaluated and cached on success
+pub fn cached<E>(
+    cache: Option<&ResultCache>,
+    key: Vec<u8>,
+    eval: impl FnOnce() -> Result<FeatureValues, E>,
+) -> Result<(FeatureValues, CacheStatus), E> {
+    let cache = match cache {
+        Some(ResultCache::Enabled(cache)) => cache,
+        Some(ResultCache::Disabled) | None => return Ok((eval()?, CacheStatus::Bypass)),
+    };
+    if let Some(features) = cache.get(&key) {
+        return Ok((features, CacheStatus::Hit));
+    }
+    let features = eval()?;
+    cache.insert(key, features.clone());
+    Ok((features, CacheStatus::Miss))
+}
+
+/// Manage the cache, disabled unless it is configured
+pub fn fairing() -> AdHoc {
+    AdHoc::try_on_ignite("Result cache", |rocket| async move {
+        let config = match rocket.figment().extract_inner::<CacheConfig>(CACHE_KEY) {
+            Ok(config) => config,
+            Err(e) if e.missing() => CacheConfig::default(),
+            Err(e) => {
+                error!("Invalid cache configuration: {}", e);
+                return Err(rocket);
+            }
+        };
+        match ResultCache::new(config) {
+            Ok(cache) => Ok(rocket.manage(cache)),
+            Err(e) => {
+                error!("Cannot initialize the result cache: {}", e);
+                Err(rocket)
+            }
+        }
+    })
+}
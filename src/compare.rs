@@ -0,0 +1,240 @@
+use rocket::response::status::BadRequest;
+use rocket::serde::{
+    json::{Json, Value},
+    Serialize,
+};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::registry::{self, ApiVersion};
+
+/// Relative tolerance of the value comparison
+const RELATIVE_TOLERANCE: f64 = 1e-9;
+
+/// Feature name prefixes added by `light-curve-feature` meta-features and transformers and the
+/// definition changes they tell, e.g. v0.4 `linear_fit_slope_magn` is v0.5
+/// `bins_window1.0_offset0.0_arcsinh_linear_fit_slope_magn`
+const NAME_PREFIXES: [(&str, &str); 4] = [
+    (
+        "bins_window1.0_offset0.0_",
+        "evaluated on the light curve binned to one-day bins",
+    ),
+    ("arcsinh_", "inverse hyperbolic sine of the value"),
+    ("lg_", "decimal logarithm of the value"),
+    ("ln1p_", "natural logarithm of one plus the value"),
+];
+
+/// Correspondence of a feature between two versions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Feature exists in both versions, the values are not compared
+    Common,
+    /// Feature exists in both versions and has the same value
+    Same,
+    /// Feature exists in both versions and has different values, so its definition or
+    /// transformation has changed
+    Changed,
+    /// Feature exists in both versions under different names, `changes` tell how the definition
+    /// or transformation has changed
+    Renamed,
+    /// Feature exists in the old version only
+    Removed,
+    /// Feature exists in the new version only
+    Added,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeatureMapping {
+    name: String,
+    status: Status,
+    /// Name in the old version of a renamed feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<&'static str>,
+}
+
+impl FeatureMapping {
+    fn new(name: String, status: Status) -> Self {
+        Self {
+            name,
+            status,
+            from_name: None,
+            changes: vec![],
+        }
+    }
+}
+
+/// Feature values of both versions evaluated for the same light curve
+#[derive(Clone, Copy)]
+struct Values<'a> {
+    from: &'a HashMap<String, f64>,
+    to: &'a HashMap<String, f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionFeatures {
+    name: &'static str,
+    light_curve_feature_version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    features: Option<HashMap<String, f64>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    from: VersionFeatures,
+    to: VersionFeatures,
+    mapping: Vec<FeatureMapping>,
+}
+
+fn find_version(name: &str) -> Result<&'static dyn ApiVersion, BadRequest<String>> {
+    registry::find(name).ok_or_else(|| {
+        BadRequest(Some(format!(
+            "Bad request: unknown version {:?}, known versions are {}",
+            name,
+            registry::names().join(", ")
+        )))
+    })
+}
+
+fn values_equal(a: f64, b: f64) -> bool {
+    (a.is_nan() && b.is_nan()) || (a - b).abs() <= RELATIVE_TOLERANCE * f64::max(a.abs(), b.abs())
+}
+
+/// Name without the prefixes of `NAME_PREFIXES` and the definition changes they tell
+fn strip_prefixes(mut name: &str) -> (&str, Vec<&'static str>) {
+    let mut changes = vec![];
+    while let Some((rest, change)) = NAME_PREFIXES
+        .iter()
+        .find_map(|&(prefix, change)| Some((name.strip_prefix(prefix)?, change)))
+    {
+        name = rest;
+        changes.push(change);
+    }
+    (name, changes)
+}
+
+/// Feature correspondence by name, values are compared if given
+///
+/// Features existing in one version only are renamed if they differ by the prefixes of
+/// `NAME_PREFIXES` only
+fn mapping(
+    from: &dyn ApiVersion,
+    to: &dyn ApiVersion,
+    values: Option<Values>,
+) -> Vec<FeatureMapping> {
+    let from_names: BTreeSet<String> = from.feature_names().into_iter().collect();
+    let to_names: BTreeSet<String> = to.feature_names().into_iter().collect();
+    let removed: BTreeSet<&str> = from_names
+        .difference(&to_names)
+        .map(|s| s.as_str())
+        .collect();
+    let added: BTreeSet<&str> = to_names
+        .difference(&from_names)
+        .map(|s| s.as_str())
+        .collect();
+    let renamed_mapping = |from_name: &str, to_name: &str, changes| FeatureMapping {
+        from_name: Some(from_name.to_owned()),
+        changes,
+        ..FeatureMapping::new(to_name.to_owned(), Status::Renamed)
+    };
+
+    // Prefixes may be either added or removed
+    let mut renamed = vec![];
+    for &name in added.iter() {
+        let (plain, changes) = strip_prefixes(name);
+        if !changes.is_empty() && removed.contains(plain) {
+            renamed.push(renamed_mapping(plain, name, changes));
+        }
+    }
+    for &name in removed.iter() {
+        let (plain, changes) = strip_prefixes(name);
+        if !changes.is_empty() && added.contains(plain) {
+            renamed.push(renamed_mapping(name, plain, changes));
+        }
+    }
+    let renamed_names: BTreeSet<String> = renamed
+        .iter()
+        .flat_map(|mapping| [mapping.name.clone(), mapping.from_name.clone().unwrap()])
+        .collect();
+
+    let mut mapping: Vec<_> = from_names
+        .union(&to_names)
+        .filter(|&name| !renamed_names.contains(name))
+        .map(|name| {
+            let status = match (from_names.contains(name), to_names.contains(name)) {
+                (true, false) => Status::Removed,
+                (false, true) => Status::Added,
+                _ => match values {
+                    Some(Values { from, to }) => match (from.get(name), to.get(name)) {
+                        (Some(&a), Some(&b)) if values_equal(a, b) => Status::Same,
+                        _ => Status::Changed,
+                    },
+                    None => Status::Common,
+                },
+            };
+            FeatureMapping::new(name.clone(), status)
+        })
+        .collect();
+    mapping.extend(renamed);
+    mapping.sort_by(|a, b| a.name.cmp(&b.name));
+    mapping
+}
+
+/// Static comparison of the default feature sets of two versions
+///
+/// Renamed features tell how their definitions or transformations have changed
+#[get("/compare?<from>&<to>")]
+pub fn describe(from: &str, to: &str) -> Result<Json<Comparison>, BadRequest<String>> {
+    let (from, to) = (find_version(from)?, find_version(to)?);
+    Ok(Json(Comparison {
+        from: VersionFeatures {
+            name: from.name(),
+            light_curve_feature_version: from.full_version(),
+            features: None,
+        },
+        to: VersionFeatures {
+            name: to.name(),
+            light_curve_feature_version: to.full_version(),
+            features: None,
+        },
+        mapping: mapping(from, to, None),
+    }))
+}
+
+/// Default feature sets of two versions evaluated for the same light curve
+///
+/// Features existing in both versions are `same` or `changed` depending on their values
+#[post("/compare?<from>&<to>", format = "application/json", data = "<data>")]
+pub fn compare(
+    from: &str,
+    to: &str,
+    data: Json<Value>,
+) -> Result<Json<Comparison>, BadRequest<String>> {
+    let (from, to) = (find_version(from)?, find_version(to)?);
+    let eval = |version: &dyn ApiVersion| {
+        version.eval(data.0.clone()).map_err(|e| {
+            let e = e.trim_start_matches("Bad request: ");
+            BadRequest(Some(format!("Bad request: {}: {}", version.name(), e)))
+        })
+    };
+    let (from_values, to_values) = (eval(from)?, eval(to)?);
+    let values = Values {
+        from: &from_values,
+        to: &to_values,
+    };
+    let mapping = mapping(from, to, Some(values));
+    Ok(Json(Comparison {
+        from: VersionFeatures {
+            name: from.name(),
+            light_curve_feature_version: from.full_version(),
+            features: Some(from_values),
+        },
+        to: VersionFeatures {
+            name: to.name(),
+            light_curve_feature_version: to.full_version(),
+            features: Some(to_values),
+        },
+        mapping,
+    }))
+}
@@ -0,0 +1,239 @@
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::figment::Figment;
+use rocket::http::{uri::Origin, ContentType, Header, Method, Status};
+use rocket::serde::Deserialize;
+use rocket::{Build, Data, Request, Response, Rocket};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use time::{format_description::well_known::Iso8601, Date, OffsetDateTime};
+
+use crate::registry::{self, ApiVersion, Deprecation};
+
+/// Rocket configuration key of the strict mode, e.g. `ROCKET_STRICT_SUNSET=true`
+pub const STRICT_SUNSET_KEY: &str = "strict_sunset";
+
+/// Rocket configuration key of the per-version deprecation notices overriding the registry ones,
+/// e.g. `ROCKET_DEPRECATIONS={v0_1={sunset="2024-12-31"}}`
+///
+/// Versions are named with underscores instead of dots, each one may have `sunset` and `successor`
+pub const DEPRECATIONS_KEY: &str = "deprecations";
+
+/// Path requests past the sunset date are rerouted to in the strict mode
+const GONE_PATH: &str = "/api/gone";
+
+/// Version of the request, cached by the request fairing for the response fairing
+#[derive(Clone)]
+struct RequestedVersion(Option<(&'static dyn ApiVersion, Deprecation)>);
+
+/// Configured deprecation notice of a version, missing fields are taken from the registry
+#[derive(Debug, Deserialize)]
+struct DeprecationConfig {
+    #[serde(default)]
+    sunset: Option<String>,
+    #[serde(default)]
+    successor: Option<String>,
+}
+
+/// Deprecation notices of the registry versions with the configuration applied
+pub struct Notices(Vec<Option<Deprecation>>);
+
+impl Notices {
+    pub fn new(figment: &Figment) -> Result<Self, String> {
+        let mut config: HashMap<String, DeprecationConfig> =
+            match figment.extract_inner(DEPRECATIONS_KEY) {
+                Ok(config) => config,
+                Err(e) if e.missing() => HashMap::new(),
+                Err(e) => return Err(e.to_string()),
+            };
+        let notices = registry::VERSIONS
+            .iter()
+            .map(|version| {
+                let default = version.deprecation();
+                let notice = match config.remove(&version.name().replace('.', "_")) {
+                    Some(DeprecationConfig { sunset, successor }) => Some(Deprecation {
+                        successor: successor
+                            .or_else(|| default.as_ref().map(|d| d.successor.clone()))
+                            .unwrap_or_else(|| registry::latest().name().to_owned()),
+                        sunset: sunset.or_else(|| default.and_then(|d| d.sunset)),
+                    }),
+                    None => default,
+                };
+                if let Some(notice) = &notice {
+                    sunset_date(notice).map_err(|e| format!("API {}: {}", version.name(), e))?;
+                    if registry::find(&notice.successor).is_none() {
+                        return Err(format!(
+                            "API {}: unknown successor {:?}",
+                            version.name(),
+                            notice.successor
+                        ));
+                    }
+                }
+                Ok(notice)
+            })
+            .collect::<Result<_, String>>()?;
+        match config.keys().next() {
+            Some(name) => Err(format!("unknown API version {:?}", name)),
+            None => Ok(Self(notices)),
+        }
+    }
+
+    pub fn get(&self, version: &dyn ApiVersion) -> Option<&Deprecation> {
+        registry::VERSIONS
+            .iter()
+            .position(|v| v.name() == version.name())
+            .and_then(|index| self.0[index].as_ref())
+    }
+}
+
+/// Answer of the rerouted requests, the response fairing fills the body
+#[get("/gone")]
+pub fn gone() -> Status {
+    Status::Gone
+}
+
+/// Version of the request path, `/` is served by the oldest version
+fn requested_version(path: &str) -> Option<(usize, &'static dyn ApiVersion)> {
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+    let version = match (segments.next(), segments.next()) {
+        (None, _) => registry::VERSIONS[0],
+        (Some("api"), Some(name)) => registry::find(name)?,
+        _ => return None,
+    };
+    registry::VERSIONS
+        .iter()
+        .position(|v| v.name() == version.name())
+        .map(|index| (index, version))
+}
+
+fn sunset_date(deprecation: &Deprecation) -> Result<Option<Date>, String> {
+    deprecation
+        .sunset
+        .as_deref()
+        .map(|sunset| {
+            Date::parse(sunset, &Iso8601::DEFAULT)
+                .map_err(|e| format!("invalid sunset date {:?}: {}", sunset, e))
+        })
+        .transpose()
+}
+
+/// HTTP-date of the midnight
+fn http_date(date: Date) -> String {
+    format!(
+        "{}, {:02} {} {} 00:00:00 GMT",
+        &date.weekday().to_string()[..3],
+        date.day(),
+        &date.month().to_string()[..3],
+        date.year()
+    )
+}
+
+fn is_past_sunset(deprecation: &Deprecation) -> bool {
+    match sunset_date(deprecation) {
+        Ok(Some(date)) => OffsetDateTime::now_utc().date() >= date,
+        _ => false,
+    }
+}
+
+/// Deprecation, Sunset and Link headers of the legacy API versions and per-version usage logging
+///
+/// In the strict mode requests to versions past their sunset date are answered with 410 Gone
+pub struct Deprecations {
+    strict: bool,
+    counts: Vec<AtomicUsize>,
+}
+
+impl Deprecations {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            counts: registry::VERSIONS
+                .iter()
+                .map(|_| AtomicUsize::new(0))
+                .collect(),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for Deprecations {
+    fn info(&self) -> Info {
+        Info {
+            name: "API version deprecations",
+            kind: Kind::Ignite | Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        match Notices::new(rocket.figment()) {
+            Ok(notices) => Ok(rocket.manage(notices)),
+            Err(e) => {
+                error!("Invalid deprecation configuration: {}", e);
+                Err(rocket)
+            }
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        let (index, version) = match requested_version(req.uri().path().as_str()) {
+            Some(found) => found,
+            None => return,
+        };
+        let count = self.counts[index].fetch_add(1, Ordering::Relaxed) + 1;
+        info!(
+            "API {}: request #{} to {} from {} ({})",
+            version.name(),
+            count,
+            req.uri(),
+            req.client_ip()
+                .map_or_else(|| "unknown address".to_owned(), |ip| ip.to_string()),
+            req.headers()
+                .get_one("User-Agent")
+                .unwrap_or("unknown agent"),
+        );
+
+        let deprecation = match req
+            .rocket()
+            .state::<Notices>()
+            .and_then(|notices| notices.get(version))
+        {
+            Some(deprecation) => deprecation.clone(),
+            None => return,
+        };
+        let past_sunset = is_past_sunset(&deprecation);
+        req.local_cache(|| RequestedVersion(Some((version, deprecation))));
+        if self.strict && past_sunset {
+            req.set_method(Method::Get);
+            req.set_uri(Origin::parse(GONE_PATH).unwrap());
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let (version, deprecation) = match req.local_cache(|| RequestedVersion(None)) {
+            RequestedVersion(Some((version, deprecation))) => (*version, deprecation),
+            RequestedVersion(None) => return,
+        };
+        res.set_header(Header::new("Deprecation", "true"));
+        if let Ok(Some(date)) = sunset_date(deprecation) {
+            res.set_header(Header::new("Sunset", http_date(date)));
+        }
+        res.set_header(Header::new(
+            "Link",
+            format!(
+                r#"</api/{}/>; rel="successor-version""#,
+                deprecation.successor
+            ),
+        ));
+        if req.uri().path().as_str() == GONE_PATH {
+            let message = format!(
+                "Gone: API {} was removed on {}, use {} instead",
+                version.name(),
+                deprecation.sunset.as_deref().unwrap_or_default(),
+                deprecation.successor
+            );
+            res.set_status(Status::Gone);
+            res.set_header(ContentType::Plain);
+            res.set_sized_body(message.len(), Cursor::new(message));
+        }
+    }
+}
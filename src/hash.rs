@@ -0,0 +1,17 @@
+/// 64-bit FNV-1a hash
+///
+/// Unlike `std::collections::hash_map::DefaultHasher` its output is stable between Rust releases
+/// and platforms, so it can be stored alongside feature values
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Hex-encoded FNV-1a hash
+pub fn hex_digest(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(bytes))
+}
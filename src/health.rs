@@ -0,0 +1,203 @@
+use rocket::http::Status;
+use rocket::serde::{
+    json::{serde_json, Json, Value},
+    Serialize,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::meta::SERVICE_VERSION;
+use crate::registry::{self, ApiVersion};
+use crate::warm_up::{self, WarmUpReport};
+
+const GIT_COMMIT: &str = env!("GIT_COMMIT");
+const RUSTC_VERSION: &str = env!("RUSTC_VERSION");
+const TARGET: &str = env!("TARGET");
+
+/// Number of observations of the canary light curve
+const CANARY_SIZE: usize = 50;
+
+/// Set after the first successful readiness check
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Periodic light curve exercising periodograms and parametric fits
+pub fn canary_light_curve() -> Value {
+    let light_curve: Vec<_> = (0..CANARY_SIZE)
+        .map(|i| {
+            let t = 1.3 * i as f64;
+            serde_json::json!({
+                "t": t,
+                "m": 15.0 + 0.5 * f64::sin(2.0 * std::f64::consts::PI * t / 7.0),
+                "err": 0.05,
+            })
+        })
+        .collect();
+    serde_json::json!({ "light_curve": light_curve })
+}
+
+#[derive(Debug, Serialize)]
+pub struct Health {
+    status: &'static str,
+}
+
+/// The process is alive
+#[get("/health")]
+pub fn health() -> Json<Health> {
+    Json(Health { status: "ok" })
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionReadiness {
+    name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    ready: bool,
+    versions: Vec<VersionReadiness>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warm_up: Option<WarmUpReport>,
+}
+
+/// Default extractors of all versions evaluate the canary light curve
+///
+/// Extractors are thread-local, so the check initializes the ones of the current worker thread
+#[get("/ready")]
+pub fn ready() -> (Status, Json<Readiness>) {
+    let versions: Vec<_> = registry::VERSIONS
+        .iter()
+        .map(|version| VersionReadiness {
+            name: version.name(),
+            error: if READY.load(Ordering::Relaxed) {
+                None
+            } else {
+                version.eval(canary_light_curve()).err()
+            },
+        })
+        .collect();
+    let ready = versions.iter().all(|version| version.error.is_none());
+    READY.fetch_or(ready, Ordering::Relaxed);
+    let status = if ready {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (
+        status,
+        Json(Readiness {
+            ready,
+            versions,
+            warm_up: warm_up::report(),
+        }),
+    )
+}
+
+/// x86-64 microarchitecture level given by the feature sets of the levels 2, 3 and 4
+#[cfg(target_arch = "x86_64")]
+fn x86_64_level(v2: bool, v3: bool, v4: bool) -> &'static str {
+    match (v2, v3, v4) {
+        (true, true, true) => "x86-64-v4",
+        (true, true, false) => "x86-64-v3",
+        (true, false, _) => "x86-64-v2",
+        (false, _, _) => "x86-64",
+    }
+}
+
+/// CPU feature level the binary is compiled for
+#[cfg(target_arch = "x86_64")]
+fn compiled_cpu_level() -> &'static str {
+    x86_64_level(
+        cfg!(all(
+            target_feature = "popcnt",
+            target_feature = "sse4.2",
+            target_feature = "ssse3"
+        )),
+        cfg!(all(
+            target_feature = "avx2",
+            target_feature = "bmi2",
+            target_feature = "fma"
+        )),
+        cfg!(all(
+            target_feature = "avx512bw",
+            target_feature = "avx512cd",
+            target_feature = "avx512dq",
+            target_feature = "avx512f",
+            target_feature = "avx512vl"
+        )),
+    )
+}
+
+/// CPU feature level of the host
+#[cfg(target_arch = "x86_64")]
+fn detected_cpu_level() -> &'static str {
+    x86_64_level(
+        is_x86_feature_detected!("popcnt")
+            && is_x86_feature_detected!("sse4.2")
+            && is_x86_feature_detected!("ssse3"),
+        is_x86_feature_detected!("avx2")
+            && is_x86_feature_detected!("bmi2")
+            && is_x86_feature_detected!("fma"),
+        is_x86_feature_detected!("avx512bw")
+            && is_x86_feature_detected!("avx512cd")
+            && is_x86_feature_detected!("avx512dq")
+            && is_x86_feature_detected!("avx512f")
+            && is_x86_feature_detected!("avx512vl"),
+    )
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn compiled_cpu_level() -> &'static str {
+    std::env::consts::ARCH
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn detected_cpu_level() -> &'static str {
+    std::env::consts::ARCH
+}
+
+#[derive(Debug, Serialize)]
+pub struct CpuLevel {
+    compiled: &'static str,
+    detected: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionInfo {
+    name: &'static str,
+    light_curve_feature_version: &'static str,
+    backends: &'static [&'static str],
+}
+
+#[derive(Debug, Serialize)]
+pub struct Info {
+    service_version: &'static str,
+    git_commit: &'static str,
+    rustc_version: &'static str,
+    target: &'static str,
+    cpu_level: CpuLevel,
+    versions: Vec<VersionInfo>,
+}
+
+/// Build-time data
+#[get("/info")]
+pub fn info() -> Json<Info> {
+    Json(Info {
+        service_version: SERVICE_VERSION,
+        git_commit: GIT_COMMIT,
+        rustc_version: RUSTC_VERSION,
+        target: TARGET,
+        cpu_level: CpuLevel {
+            compiled: compiled_cpu_level(),
+            detected: detected_cpu_level(),
+        },
+        versions: registry::VERSIONS
+            .iter()
+            .map(|version| VersionInfo {
+                name: version.name(),
+                light_curve_feature_version: version.full_version(),
+                backends: version.backends(),
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,41 @@
+use rocket::data::{self, Data, FromData};
+use rocket::outcome::Outcome;
+use rocket::serde::json::{self, Json};
+use rocket::serde::Deserialize;
+use rocket::Request;
+
+/// Deserialization error of the request body
+struct BodyError(Option<String>);
+
+/// JSON request body, its deserialization error is reported by the `422` catcher
+///
+/// Errors of the input validation done while deserializing, e.g. of times or coordinates, are
+/// deserialization errors too
+pub struct JsonInput<T>(pub T);
+
+#[rocket::async_trait]
+impl<'r, T: Deserialize<'r>> FromData<'r> for JsonInput<T> {
+    type Error = json::Error<'r>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        match <Json<T> as FromData<'r>>::from_data(req, data).await {
+            Outcome::Success(Json(value)) => Outcome::Success(Self(value)),
+            Outcome::Failure((status, e)) => {
+                if let json::Error::Parse(_, parse_error) = &e {
+                    req.local_cache(|| BodyError(Some(parse_error.to_string())));
+                }
+                Outcome::Failure((status, e))
+            }
+            Outcome::Forward(data) => Outcome::Forward(data),
+        }
+    }
+}
+
+/// Plain-text response with the deserialization error of the request body if there is one
+#[catch(422)]
+pub fn unprocessable_entity(req: &Request) -> String {
+    match req.local_cache(|| BodyError(None)) {
+        BodyError(Some(e)) => format!("Unprocessable entity: {}", e),
+        BodyError(None) => "Unprocessable entity".into(),
+    }
+}
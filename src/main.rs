@@ -1,14 +1,27 @@
+mod access_log;
+mod cache;
+mod compare;
+mod deprecation;
+mod hash;
+mod health;
+mod json_input;
+mod meta;
+mod registry;
 #[cfg(test)]
 mod tests;
 mod v0_1;
 mod v0_2;
 mod v0_4;
 mod v0_5;
+mod warm_up;
 
 #[macro_use]
 extern crate rocket;
 use rocket::response::Redirect;
 use rocket::serde::json::Json;
+use rocket::tokio::runtime::Builder;
+use rocket::{Build, Config, Rocket};
+use std::time::Duration;
 
 #[get("/help")]
 fn help() -> Redirect {
@@ -16,22 +29,61 @@ fn help() -> Redirect {
 }
 
 #[get("/versions")]
-fn versions() -> Json<&'static [&'static str]> {
-    Json(&["v0.1", "v0.2", "v0.4", "v0.5", "latest"])
+fn versions() -> Json<Vec<String>> {
+    Json(registry::names())
 }
 
-#[launch]
-fn rocket() -> _ {
-    rocket::build()
+fn rocket() -> Rocket<Build> {
+    let rocket = rocket::build();
+    let strict_sunset = rocket
+        .figment()
+        .extract_inner(deprecation::STRICT_SUNSET_KEY)
+        .unwrap_or(false);
+    let rocket = rocket
         // Pre-defined features
-        .mount("/", routes![v0_1::index, help, versions])
-        .mount("/api/v0.1.17/", routes![v0_1::index])
-        .mount("/api/v0.1/", routes![v0_1::index])
-        .mount("/api/v0.2.2/", routes![v0_2::index])
-        .mount("/api/v0.2/", routes![v0_2::index])
-        .mount("/api/v0.4.5/", routes![v0_4::index])
-        .mount("/api/v0.4/", routes![v0_4::index])
-        .mount("/api/v0.5.5/", routes![v0_5::index, v0_5::features])
-        .mount("/api/v0.5/", routes![v0_5::index, v0_5::features])
-        .mount("/api/latest/", routes![v0_5::index, v0_5::features])
+        .mount(
+            "/",
+            routes![
+                v0_1::index,
+                health::health,
+                health::info,
+                health::ready,
+                help,
+                versions
+            ],
+        )
+        .mount(
+            "/api/",
+            routes![
+                compare::describe,
+                compare::compare,
+                deprecation::gone,
+                registry::feature_names,
+                registry::describe,
+                v0_5::dmdt::dmdt
+            ],
+        );
+    registry::mount(rocket)
+        .register("/", catchers![json_input::unprocessable_entity])
+        .attach(deprecation::Deprecations::new(strict_sunset))
+        .attach(cache::fairing())
+        .attach(warm_up::WarmUp)
+        .attach(access_log::AccessLog)
+}
+
+/// Same runtime as `#[launch]` builds, its threads warm up thread-local extractors when they start
+fn main() {
+    let config = Config::from(Config::figment());
+    let runtime = Builder::new_multi_thread()
+        .thread_name("rocket-worker-thread")
+        .worker_threads(config.workers)
+        .max_blocking_threads(config.max_blocking)
+        .on_thread_start(warm_up::warm_up_thread)
+        .enable_all()
+        .build()
+        .expect("create tokio runtime");
+    let _ = runtime.block_on(rocket().launch());
+    if config.shutdown.force {
+        runtime.shutdown_timeout(Duration::from_millis(500));
+    }
 }
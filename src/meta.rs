@@ -0,0 +1,91 @@
+use rocket::serde::Serialize;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::v0_5::ephemeris::TimeCorrectionRange;
+
+/// Version of this service
+pub const SERVICE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Provenance of a feature vector, returned on request with `?meta=true`
+#[derive(Debug, Serialize)]
+pub struct Meta {
+    /// Version of this service
+    pub service_version: &'static str,
+    /// Exact version of `light-curve-feature` used by the route
+    pub light_curve_feature_version: &'static str,
+    /// Hash of the JSON-serialized extractor definition
+    pub extractor_hash: String,
+    /// Number of observations in the request
+    pub n_obs_input: usize,
+    /// Number of observations passed to the extractor
+    pub n_obs_used: usize,
+    /// Indices of the observations removed by preprocessing, in the request order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_indices: Vec<usize>,
+    /// Light-travel time corrections applied to the observation times
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_correction: Option<TimeCorrectionRange>,
+    /// Random seed of the bootstrap resampling, MCMC curve fits of `light-curve-feature` v0.5.5
+    /// always use the same fixed seed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Time span of the used observations
+    pub time_span: f64,
+    /// Magnitude zero point used to convert magnitudes to fluxes, if any
+    pub zero_point: Option<f64>,
+    /// Unix time of the evaluation end, in seconds
+    pub evaluated_at: f64,
+    /// Wall-clock evaluation time, in milliseconds
+    pub evaluation_time_ms: f64,
+}
+
+impl Meta {
+    pub fn new(
+        light_curve_feature_version: &'static str,
+        extractor_hash: String,
+        n_obs_input: usize,
+        t: &[f64],
+        zero_point: Option<f64>,
+        started: Instant,
+    ) -> Self {
+        let time_span = match (t.first(), t.last()) {
+            (Some(t_min), Some(t_max)) => t_max - t_min,
+            _ => 0.0,
+        };
+        Self {
+            service_version: SERVICE_VERSION,
+            light_curve_feature_version,
+            extractor_hash,
+            n_obs_input,
+            n_obs_used: t.len(),
+            removed_indices: vec![],
+            time_correction: None,
+            seed: None,
+            time_span,
+            zero_point,
+            evaluated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(f64::NAN),
+            evaluation_time_ms: 1e3 * started.elapsed().as_secs_f64(),
+        }
+    }
+
+    pub fn with_removed_indices(self, removed_indices: Vec<usize>) -> Self {
+        Self {
+            removed_indices,
+            ..self
+        }
+    }
+
+    pub fn with_time_correction(self, time_correction: Option<TimeCorrectionRange>) -> Self {
+        Self {
+            time_correction,
+            ..self
+        }
+    }
+
+    pub fn with_seed(self, seed: Option<u64>) -> Self {
+        Self { seed, ..self }
+    }
+}
@@ -0,0 +1,134 @@
+use rocket::serde::{
+    json::{Json, Value},
+    Serialize,
+};
+use rocket::{Build, Rocket, Route, State};
+use std::collections::HashMap;
+
+use crate::deprecation::Notices;
+use crate::{v0_1, v0_2, v0_4, v0_5};
+
+/// Deprecation notice of an API version
+#[derive(Debug, Clone, Serialize)]
+pub struct Deprecation {
+    /// Date when the version is removed, ISO-8601
+    pub sunset: Option<String>,
+    /// Name of the version to migrate to
+    pub successor: String,
+}
+
+/// Feature extraction API backed by a `light-curve-feature` release
+pub trait ApiVersion: Sync {
+    /// Short version, the API is mounted at `/api/<name>/`
+    fn name(&self) -> &'static str;
+
+    /// Exact `light-curve-feature` version, the API is also mounted at `/api/v<full_version>/`
+    fn full_version(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    fn routes(&self) -> Vec<Route>;
+
+    /// Names of the default feature set
+    fn feature_names(&self) -> Vec<String>;
+
+    /// Enabled `light-curve-feature` Cargo features, must follow Cargo.toml
+    fn backends(&self) -> &'static [&'static str];
+
+    /// Default feature set of a JSON request body
+    fn eval(&self, data: Value) -> Result<HashMap<String, f64>, String>;
+
+    /// Default deprecation notice, the configuration may override it
+    fn deprecation(&self) -> Option<Deprecation> {
+        None
+    }
+
+    /// Mount paths of the version
+    fn paths(&self) -> Vec<String> {
+        vec![
+            format!("/api/{}/", self.name()),
+            format!("/api/v{}/", self.full_version()),
+        ]
+    }
+}
+
+/// All API versions in ascending order, the last one is also mounted as `latest`
+pub static VERSIONS: [&dyn ApiVersion; 4] = [
+    &v0_1::Version,
+    &v0_2::Version,
+    &v0_4::Version,
+    &v0_5::Version,
+];
+
+pub const LATEST: &str = "latest";
+
+pub fn latest() -> &'static dyn ApiVersion {
+    VERSIONS[VERSIONS.len() - 1]
+}
+
+/// Version by its short or full name or `latest`
+pub fn find(name: &str) -> Option<&'static dyn ApiVersion> {
+    if name == LATEST {
+        return Some(latest());
+    }
+    VERSIONS
+        .iter()
+        .copied()
+        .find(|version| name == version.name() || name == format!("v{}", version.full_version()))
+}
+
+/// Mount routes of all versions
+pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
+    VERSIONS
+        .iter()
+        .fold(rocket, |rocket, version| {
+            version
+                .paths()
+                .into_iter()
+                .fold(rocket, |rocket, path| rocket.mount(path, version.routes()))
+        })
+        .mount(format!("/api/{}/", LATEST), latest().routes())
+}
+
+/// Names of all mount points: short and full versions and `latest`
+pub fn names() -> Vec<String> {
+    VERSIONS
+        .iter()
+        .flat_map(|version| {
+            vec![
+                version.name().to_owned(),
+                format!("v{}", version.full_version()),
+            ]
+        })
+        .chain(std::iter::once(LATEST.to_owned()))
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct Description {
+    name: &'static str,
+    light_curve_feature_version: &'static str,
+    description: &'static str,
+    feature_names: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecation: Option<Deprecation>,
+}
+
+/// Names of the default feature set of the version
+#[get("/<version>/names")]
+pub fn feature_names(version: &str) -> Option<Json<Vec<String>>> {
+    find(version).map(|version| Json(version.feature_names()))
+}
+
+#[get("/<version>/describe")]
+pub fn describe(version: &str, notices: &State<Notices>) -> Option<Json<Description>> {
+    find(version).map(|version| {
+        Json(Description {
+            name: version.name(),
+            light_curve_feature_version: version.full_version(),
+            description: version.description(),
+            feature_names: version.feature_names(),
+            deprecation: notices.get(version).cloned(),
+        })
+    })
+}
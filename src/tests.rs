@@ -18,5 +18,829 @@ fn versions() {
         let resp = req.dispatch();
         let status = resp.status();
         assert_ne!(status.code, 404, "{:?}", status.reason());
+
+        let names = client
+            .get(format!("/api/{}/names", version))
+            .dispatch()
+            .into_json::<Vec<String>>()
+            .unwrap();
+        assert!(!names.is_empty());
+    }
+}
+
+/// Check that `/versions` is generated from the registry
+#[test]
+fn registry() {
+    use crate::registry::{latest, names, VERSIONS};
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let versions = client
+        .get("/versions")
+        .dispatch()
+        .into_json::<Vec<String>>()
+        .unwrap();
+    assert_eq!(versions, names());
+    for version in VERSIONS.iter() {
+        assert!(versions.contains(&version.name().to_owned()));
+        assert!(versions.contains(&format!("v{}", version.full_version())));
+    }
+    assert_eq!(latest().name(), "v0.5");
+}
+
+/// Check deprecation headers of legacy versions
+#[test]
+fn deprecation() {
+    let client = Client::tracked(super::rocket()).unwrap();
+    for path in ["/", "/api/v0.1/"] {
+        let resp = client.post(path).header(ContentType::JSON).dispatch();
+        assert_eq!(resp.headers().get_one("Deprecation"), Some("true"));
+        assert!(resp.headers().get_one("Link").is_some());
+    }
+    let resp = client
+        .post("/api/latest/")
+        .header(ContentType::JSON)
+        .dispatch();
+    assert!(resp.headers().get_one("Deprecation").is_none());
+}
+
+/// Check that the configuration overrides deprecation notices of the registry
+#[test]
+fn deprecation_config() {
+    use crate::deprecation::Notices;
+    use crate::registry::find;
+    use rocket::figment::Figment;
+
+    let figment = Figment::new().merge(("deprecations.v0_1.sunset", "2100-01-01"));
+    let notices = Notices::new(&figment).unwrap();
+    let notice = notices.get(find("v0.1").unwrap()).unwrap();
+    assert_eq!(notice.sunset.as_deref(), Some("2100-01-01"));
+    assert_eq!(notice.successor, "v0.5");
+    assert!(notices.get(find("v0.5").unwrap()).is_none());
+
+    let figment = Figment::new().merge(("deprecations.v0_9.sunset", "2100-01-01"));
+    assert!(Notices::new(&figment).is_err());
+}
+
+/// Check health, readiness and build-info endpoints
+#[test]
+fn health() {
+    use rocket::http::Status;
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    assert_eq!(client.get("/health").dispatch().status(), Status::Ok);
+    let ready = client.get("/ready").dispatch();
+    assert_eq!(ready.status(), Status::Ok);
+    let ready = ready.into_json::<Value>().unwrap();
+    assert!(ready["warm_up"]["threads"].as_u64().unwrap() > 0);
+    let info = client.get("/info").dispatch().into_json::<Value>().unwrap();
+    assert!(info["git_commit"].is_string());
+}
+
+fn light_curve_json(n: usize) -> String {
+    let observations: Vec<_> = (0..n)
+        .map(|i| {
+            format!(
+                r#"{{"t": {}, "m": {}, "err": 0.1}}"#,
+                i as f64,
+                15.0 + (0.3 * i as f64).sin()
+            )
+        })
+        .collect();
+    format!(r#"{{"light_curve": [{}]}}"#, observations.join(", "))
+}
+
+/// Check that responses tell the cache status, the cache is disabled by default
+#[test]
+fn cache() {
+    let client = Client::tracked(super::rocket()).unwrap();
+    let resp = client
+        .post("/api/v0.5/")
+        .header(ContentType::JSON)
+        .body(light_curve_json(20))
+        .dispatch();
+    assert_eq!(resp.headers().get_one("X-Cache"), Some("bypass"));
+
+    let body = format!(
+        r#"{{"extractor": {{"Amplitude": {{}}}}, {}"#,
+        &light_curve_json(20)[1..]
+    );
+    let resp = client
+        .post("/api/v0.5/features")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch();
+    assert_eq!(resp.headers().get_one("X-Cache"), Some("bypass"));
+}
+
+/// Check that metadata is opt-in
+#[test]
+fn meta() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let body = light_curve_json(20);
+
+    let without_meta = client
+        .post("/api/v0.5/")
+        .header(ContentType::JSON)
+        .body(&body)
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    assert!(without_meta.get("meta").is_none());
+    assert!(without_meta.get("id").is_none());
+
+    let with_meta = client
+        .post("/api/v0.5/?meta=true")
+        .header(ContentType::JSON)
+        .body(&body)
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    let meta = with_meta.get("meta").unwrap();
+    assert_eq!(meta["light_curve_feature_version"], "0.5.5");
+    assert_eq!(meta["n_obs_input"], 20);
+    assert_eq!(meta["time_span"], 19.0);
+}
+
+/// Check that fit diagnostics are consistent
+#[test]
+fn fit() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let body = format!(
+        r#"{{"t_grid": {{"start": 0.0, "end": 10.0, "size": 11}}, {}"#,
+        &light_curve_json(20)[1..]
+    );
+    let fit = client
+        .post("/api/v0.5/fit/bazin")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    assert_eq!(fit["parameters"].as_array().unwrap().len(), 5);
+    assert_eq!(fit["model_curve"]["t"].as_array().unwrap().len(), 11);
+}
+
+/// Check that the in-house Linexp fit recovers the model parameters and that `/transient` reports
+/// the in-house fits
+#[test]
+fn fit_in_house() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    // Linexp light curve in μJy with A = 2718, B = 10, t₀ = 0 and τ = 10
+    let observations: Vec<_> = (1..61)
+        .map(|i| {
+            let dt = i as f64 / 10.0;
+            let flux = 10.0 + 2718.0 * dt * f64::exp(-dt);
+            format!(
+                r#"{{"t": {}, "m": {}, "err": 0.01}}"#,
+                i as f64,
+                23.9 - 2.5 * flux.log10()
+            )
+        })
+        .collect();
+    let body = format!(
+        r#"{{"zero_point": "uJy", "light_curve": [{}]}}"#,
+        observations.join(", ")
+    );
+
+    let fit = client
+        .post("/api/v0.5/fit/linexp")
+        .header(ContentType::JSON)
+        .body(&body)
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    let parameters: Vec<_> = fit["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| (p["name"].as_str().unwrap(), p["value"].as_f64().unwrap()))
+        .collect();
+    assert_eq!(parameters[3].0, "rise_time");
+    assert!((parameters[3].1 - 10.0).abs() < 0.5, "{:?}", parameters);
+    assert!(parameters[2].1.abs() < 0.5, "{:?}", parameters);
+    assert!(fit["censored_iterations"].as_u64().unwrap() > 0);
+
+    let features = client
+        .post("/api/v0.5/transient")
+        .header(ContentType::JSON)
+        .body(&body)
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    for name in [
+        "linexp_fit_rise_time_flux",
+        "ln1p_linexp_fit_reduced_chi2_flux",
+        "power_law_fit_index_flux",
+        "ln1p_power_law_fit_reduced_chi2_flux",
+    ] {
+        assert!(features[name].is_number(), "{}", name);
+    }
+}
+
+/// Check that passbands with too few detections are dropped from the multi-band periodogram
+#[test]
+fn multiband_sparse_band() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let observations: Vec<_> = (0..23)
+        .map(|i| {
+            format!(
+                r#"{{"t": {}, "m": {}, "err": 0.1, "band": "{}"}}"#,
+                i as f64,
+                15.0 + (0.3 * i as f64).sin(),
+                if i < 20 { "g" } else { "r" }
+            )
+        })
+        .collect();
+    let resp = client
+        .post("/api/v0.5/periodogram/multiband")
+        .header(ContentType::JSON)
+        .body(format!(
+            r#"{{"light_curve": [{}]}}"#,
+            observations.join(", ")
+        ))
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    assert_eq!(resp["dropped_bands"], Value::from(vec!["r"]));
+    assert!(resp["peaks"][0]["bands"].get("r").is_none());
+}
+
+/// Check that bootstrap false-alarm probabilities are reproducible without a seed
+#[test]
+fn seed() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let body = format!(
+        r#"{{"fap": {{"method": "bootstrap", "niterations": 20}}, {}"#,
+        &light_curve_json(20)[1..]
+    );
+    let peaks: Vec<_> = (0..2)
+        .map(|_| {
+            client
+                .post("/api/v0.5/periodogram")
+                .header(ContentType::JSON)
+                .body(&body)
+                .dispatch()
+                .into_json::<Value>()
+                .unwrap()["peaks"]
+                .clone()
+        })
+        .collect();
+    assert_eq!(peaks[0], peaks[1]);
+}
+
+/// Check that the object identifier is echoed
+#[test]
+fn id() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let body = format!(r#"{{"id": "ZTF18abc", {}"#, &light_curve_json(20)[1..]);
+    let resp = client
+        .post("/api/v0.5/")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    assert_eq!(resp["id"], "ZTF18abc");
+}
+
+/// Check that input validation errors are reported
+#[test]
+fn unprocessable_entity() {
+    use rocket::http::Status;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let resp = client
+        .post("/api/v0.5/")
+        .header(ContentType::JSON)
+        .body(r#"{"light_curve": [{"t": 0.0, "m": 15.0}]}"#)
+        .dispatch();
+    assert_eq!(resp.status(), Status::UnprocessableEntity);
+    assert!(resp.into_string().unwrap().contains("\"err\" is required"));
+}
+
+/// Check conversion of times to days
+#[test]
+fn times() {
+    use crate::v0_5::times::{TimeFormat, TimeSettings, TimeUnit, TimeValue};
+
+    let days = |format, unit, t: TimeValue| TimeSettings { unit, format }.days(&t);
+    let assert_days = |format, unit, t: TimeValue, expected: f64| {
+        let days = days(format, unit, t).unwrap();
+        assert!((days - expected).abs() < 1e-9, "{} != {}", days, expected);
+    };
+    let number = |t: f64| TimeValue::Number(t);
+    let string = |t: &str| TimeValue::String(t.into());
+    // J2000 epoch is 2000-01-01T12:00:00 UTC and MJD 51544.5 if leap seconds are ignored
+    assert_days(TimeFormat::Mjd, TimeUnit::Days, number(51544.5), 51544.5);
+    assert_days(TimeFormat::Jd, TimeUnit::Days, number(2451545.0), 51544.5);
+    assert_days(TimeFormat::Unix, TimeUnit::Days, number(0.0), 40587.0);
+    assert_days(
+        TimeFormat::Unix,
+        TimeUnit::Days,
+        number(946728000.0),
+        51544.5,
+    );
+    for iso in [
+        "2000-01-01T12:00:00Z",
+        "2000-01-01T12:00:00",
+        "2000-01-01T14:00:00+02:00",
+    ] {
+        assert_days(TimeFormat::Iso, TimeUnit::Days, string(iso), 51544.5);
+    }
+    assert_days(
+        TimeFormat::Iso,
+        TimeUnit::Days,
+        string("2000-01-01"),
+        51544.0,
+    );
+    assert_days(TimeFormat::Number, TimeUnit::Hours, number(36.0), 1.5);
+    assert_days(TimeFormat::Number, TimeUnit::Seconds, number(43200.0), 0.5);
+    // The unit is of `number` times only
+    assert_days(TimeFormat::Mjd, TimeUnit::Hours, number(51544.5), 51544.5);
+
+    assert!(days(TimeFormat::Iso, TimeUnit::Days, number(51544.5)).is_err());
+    assert!(days(TimeFormat::Mjd, TimeUnit::Days, string("51544.5")).is_err());
+    assert!(days(TimeFormat::Iso, TimeUnit::Days, string("2000-13-01")).is_err());
+}
+
+/// Check that times are converted to days before the evaluation
+#[test]
+fn times_unit() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let body = format!(
+        r#"{{"time": {{"unit": "hours"}}, {}"#,
+        &light_curve_json(100)[1..]
+    );
+    let resp = client
+        .post("/api/v0.5/?meta=true")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    assert!((resp["meta"]["time_span"].as_f64().unwrap() - 99.0 / 24.0).abs() < 1e-12);
+}
+
+/// Check that time correction of relative times is rejected
+#[test]
+fn times_correction_number() {
+    use rocket::http::Status;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let body = format!(
+        r#"{{"time_correction": {{"ra": 83.63, "dec": 22.01}}, {}"#,
+        &light_curve_json(20)[1..]
+    );
+    let resp = client
+        .post("/api/v0.5/")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch();
+    assert_eq!(resp.status(), Status::UnprocessableEntity);
+    assert!(resp
+        .into_string()
+        .unwrap()
+        .contains("time correction requires absolute times"));
+
+    let body = format!(
+        r#"{{"time": {{"format": "mjd"}}, "time_correction": {{"ra": 83.63, "dec": 22.01}}, {}"#,
+        &light_curve_json(20)[1..]
+    );
+    let resp = client
+        .post("/api/v0.5/")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+}
+
+/// Check Baluev false-alarm probability against the reference implementation of astropy
+#[test]
+fn fap_baluev() {
+    use crate::v0_5::fap::baluev;
+
+    let t: Vec<f64> = (0..50).map(|i| i as f64).collect();
+    // Standard normalized power z and the false-alarm probability of `fap_baluev` with fmax = 0.5
+    for &(z, reference) in &[
+        (0.1, 0.9721676089496472),
+        (0.2, 0.2842108043495254),
+        (0.3, 0.01873768578450896),
+        (0.5, 1.0591906449808342e-05),
+        (0.9, 0.0),
+    ] {
+        let fap = baluev(0.5 * 49.0 * z, &t, std::f64::consts::PI);
+        assert!((fap - reference).abs() < 1e-6, "{} {}", z, fap);
+    }
+}
+
+/// Check that a sinusoid has vanishing false-alarm probability and white noise has a large one
+#[test]
+fn fap_periodogram() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let peak_fap = |m: &dyn Fn(f64) -> f64| {
+        let observations: Vec<_> = (0..50)
+            .map(|i| format!(r#"{{"t": {}, "m": {}, "err": 0.1}}"#, i, m(i as f64)))
+            .collect();
+        let resp = client
+            .post("/api/v0.5/periodogram")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"fap": {{"method": "baluev"}}, "light_curve": [{}]}}"#,
+                observations.join(", ")
+            ))
+            .dispatch()
+            .into_json::<Value>()
+            .unwrap();
+        resp["peaks"][0]["fap"].as_f64().unwrap()
+    };
+    assert!(peak_fap(&|t| 15.0 + (0.7 * t).sin()) < 1e-6);
+    // Deterministic pseudo-random noise uniform in [15, 16)
+    assert!(peak_fap(&|t| 15.0 + ((3.7 * t).sin() * 43758.5453).rem_euclid(1.0)) > 0.5);
+}
+
+/// Check light-travel time corrections against astropy `Time.light_travel_time` of IP Peg at
+/// 2013-01-29, the example of the astropy documentation
+#[test]
+fn ephemeris_light_travel_time() {
+    use crate::v0_5::ephemeris::TimeCorrection;
+    use rocket::serde::json::serde_json;
+
+    let mjd = [56325.95833333, 56325.978254];
+    for (reference, astropy) in [
+        ("barycentric", [-0.0037715, -0.00377286]),
+        ("heliocentric", [-0.00376576, -0.00376712]),
+    ] {
+        let correction: TimeCorrection = serde_json::from_str(&format!(
+            r#"{{"ra": 350.785625, "dec": 18.416472, "reference": "{}"}}"#,
+            reference
+        ))
+        .unwrap();
+        let mut corrected = mjd;
+        correction.apply(&mut corrected).unwrap();
+        for ((t, corrected), astropy) in mjd.iter().zip(corrected).zip(astropy) {
+            let seconds = (corrected - t) * 86400.0;
+            assert!(
+                (seconds - astropy * 86400.0).abs() < 10.0,
+                "{}: {} s",
+                reference,
+                seconds
+            );
+        }
+    }
+}
+
+/// Check the Earth distance at the perihelion and the aphelion of 2000 and its direction at the
+/// equinox and the solstice
+#[test]
+fn ephemeris_earth_heliocentric() {
+    use crate::v0_5::ephemeris::earth_heliocentric;
+
+    let distance = |p: [f64; 3]| (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+    assert!((distance(earth_heliocentric(51547.0)) - 0.98332).abs() < 1e-4);
+    assert!((distance(earth_heliocentric(51729.0)) - 1.01675).abs() < 1e-4);
+
+    // The Sun is at the vernal equinox, so the Earth is in the opposite direction
+    let equinox = earth_heliocentric(51623.316);
+    assert!((equinox[0] + 0.996).abs() < 1e-3, "{:?}", equinox);
+    assert!(
+        equinox[1].abs() < 1e-3 && equinox[2].abs() < 1e-3,
+        "{:?}",
+        equinox
+    );
+    // The Sun is at the northmost declination, so the Earth is to the south
+    let solstice = earth_heliocentric(51716.075);
+    assert!(solstice[0].abs() < 1e-3, "{:?}", solstice);
+    assert!(solstice[1] < -0.9 && solstice[2] < -0.4, "{:?}", solstice);
+}
+
+/// Check that outlier rejection steps remove the outliers and report their indices
+#[test]
+fn preprocessing() {
+    use rocket::http::Status;
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    // Magnitude outlier at 5 and error-bar outlier at 12
+    let observations: Vec<_> = (0..30)
+        .map(|i| {
+            format!(
+                r#"{{"t": {}, "m": {}, "err": {}}}"#,
+                i as f64,
+                if i == 5 {
+                    25.0
+                } else {
+                    15.0 + 0.1 * (0.3 * i as f64).sin()
+                },
+                if i == 12 { 5.0 } else { 0.1 }
+            )
+        })
+        .collect();
+    let removed_indices = |preprocessing: &str| {
+        let resp = client
+            .post("/api/v0.5/?meta=true")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"preprocessing": {}, "light_curve": [{}]}}"#,
+                preprocessing,
+                observations.join(", ")
+            ))
+            .dispatch();
+        assert_eq!(resp.status(), Status::Ok, "{}", preprocessing);
+        resp.into_json::<Value>().unwrap()["meta"]["removed_indices"].clone()
+    };
+    assert_eq!(
+        removed_indices(r#"[{"method": "sigma_clip"}]"#),
+        Value::from(vec![5])
+    );
+    assert_eq!(
+        removed_indices(r#"[{"method": "sigma_clip", "window": 7}]"#),
+        Value::from(vec![5])
+    );
+    assert_eq!(
+        removed_indices(r#"[{"method": "mad"}]"#),
+        Value::from(vec![5])
+    );
+    assert_eq!(
+        removed_indices(r#"[{"method": "error_bar"}]"#),
+        Value::from(vec![12])
+    );
+    assert_eq!(
+        removed_indices(r#"[{"method": "error_bar"}, {"method": "mad", "threshold": 5.0}]"#),
+        Value::from(vec![5, 12])
+    );
+
+    let resp = client
+        .post("/api/v0.5/")
+        .header(ContentType::JSON)
+        .body(format!(
+            r#"{{"preprocessing": [{{"method": "sigma_clip", "window": 2}}], "light_curve": [{}]}}"#,
+            observations.join(", ")
+        ))
+        .dispatch();
+    assert_eq!(resp.status(), Status::BadRequest);
+}
+
+/// Check harmonic fit amplitude ratio and phase difference of a two-harmonic light curve
+#[test]
+fn harmonics() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    // A₁ = 0.5, φ₁ = 0.3, A₂ = 0.2 and φ₂ = 1.5, so R21 = 0.4 and φ21 = 0.9
+    let omega = 2.0 * std::f64::consts::PI / 2.7;
+    let observations: Vec<_> = (0..100)
+        .map(|i| {
+            let t = i as f64 + 0.3 * (i as f64).sin();
+            let m = 15.0 + 0.5 * (omega * t + 0.3).sin() + 0.2 * (2.0 * omega * t + 1.5).sin();
+            format!(r#"{{"t": {}, "m": {}, "err": 0.01}}"#, t, m)
+        })
+        .collect();
+    let features = client
+        .post("/api/v0.5/?harmonics=true")
+        .header(ContentType::JSON)
+        .body(format!(
+            r#"{{"light_curve": [{}]}}"#,
+            observations.join(", ")
+        ))
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    let feature = |name: &str| features[name].as_f64().unwrap();
+    assert!((feature("harmonic_period_magn") - 2.7).abs() < 0.01);
+    assert!((feature("harmonic_amplitude_1_magn") - 0.5).abs() < 0.01);
+    assert!((feature("harmonic_r21_magn") - 0.4).abs() < 0.02);
+    assert!(feature("harmonic_r31_magn") < 0.02);
+    assert!((feature("harmonic_phi21_magn") - 0.9).abs() < 0.05);
+}
+
+/// Check dm–dt map counts, probabilities and the shape of the binary output
+#[test]
+fn dmdt() {
+    use rocket::serde::json::Value;
+    use std::convert::TryInto;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    // The last observation is out of the dt grid
+    let body = |normalization: &str| {
+        format!(
+            r#"{{"light_curve": [
+                {{"t": 0.0, "m": 15.0, "err": 0.1}},
+                {{"t": 1.2, "m": 15.3, "err": 0.1}},
+                {{"t": 3.1, "m": 16.1, "err": 0.1}},
+                {{"t": 7.5, "m": 15.2, "err": 0.1}},
+                {{"t": 20.0, "m": 15.0, "err": 0.1}}
+            ],
+            "dt": {{"scale": "linear", "start": 0.0, "end": 8.0, "size": 4}},
+            "dm": {{"scale": "linear", "start": -1.5, "end": 1.5, "size": 3}},
+            "normalization": "{}"}}"#,
+            normalization
+        )
+    };
+    let counts = vec![
+        vec![0.0, 1.0, 1.0],
+        vec![0.0, 0.0, 1.0],
+        vec![1.0, 0.0, 0.0],
+        vec![0.0, 2.0, 0.0],
+    ];
+
+    let resp = client
+        .post("/api/dmdt")
+        .header(ContentType::JSON)
+        .body(body("counts"))
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    assert_eq!(resp["map"], Value::from(counts.clone()));
+    assert_eq!(resp["dt_borders"].as_array().unwrap().len(), 5);
+    assert_eq!(resp["dm_borders"].as_array().unwrap().len(), 4);
+
+    // Probabilities are fractions of all ten pairs, including the ones out of the grid
+    let resp = client
+        .post("/api/dmdt")
+        .header(ContentType::JSON)
+        .body(body("probability"))
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    let probability: f64 = resp["map"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|row| row.as_array().unwrap())
+        .map(|x| x.as_f64().unwrap())
+        .sum();
+    assert!((probability - 0.6).abs() < 1e-12);
+
+    let resp = client
+        .post("/api/dmdt?binary=true")
+        .header(ContentType::JSON)
+        .body(body("counts"))
+        .dispatch();
+    assert_eq!(resp.headers().get_one("X-Dmdt-Shape"), Some("4,3"));
+    let bytes = resp.into_bytes().unwrap();
+    let map: Vec<f64> = bytes
+        .chunks_exact(8)
+        .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    assert_eq!(map, counts.concat());
+}
+
+/// Check that a feature renamed by binning and a transformer is mapped to its old name
+#[test]
+fn compare_renamed() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let comparison = client
+        .get("/api/compare?from=v0.4&to=v0.5")
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap();
+    let mapping = comparison["mapping"].as_array().unwrap();
+    let slope: Vec<_> = mapping
+        .iter()
+        .filter(|m| {
+            m["name"] == "linear_fit_slope_magn" || m["from_name"] == "linear_fit_slope_magn"
+        })
+        .collect();
+    assert_eq!(slope.len(), 1, "{:?}", slope);
+    assert_eq!(slope[0]["status"], "renamed");
+    assert_eq!(
+        slope[0]["name"],
+        "bins_window1.0_offset0.0_arcsinh_linear_fit_slope_magn"
+    );
+    assert_eq!(
+        slope[0]["changes"],
+        Value::from(vec![
+            "evaluated on the light curve binned to one-day bins",
+            "inverse hyperbolic sine of the value",
+        ])
+    );
+}
+
+/// Check that the zero-point unit scales the Bazin amplitude and keeps it in magnitudes
+#[test]
+fn zero_point() {
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    // Bazin light curve in μJy with A = 1000, B = 10, t₀ = 20, rise and fall times of 3 and 15
+    let observations: Vec<_> = (0..60)
+        .map(|i| {
+            let t = i as f64;
+            let flux =
+                10.0 + 1000.0 * f64::exp(-(t - 20.0) / 15.0) / (1.0 + f64::exp(-(t - 20.0) / 3.0));
+            format!(
+                r#"{{"t": {}, "m": {}, "err": 0.01}}"#,
+                t,
+                23.9 - 2.5 * flux.log10()
+            )
+        })
+        .collect();
+    let request = |path: &str, unit: &str| {
+        client
+            .post(path)
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"zero_point": "{}", "light_curve": [{}]}}"#,
+                unit,
+                observations.join(", ")
+            ))
+            .dispatch()
+            .into_json::<Value>()
+            .unwrap()
+    };
+
+    let amplitude = |unit| {
+        request("/api/v0.5/fit/bazin", unit)["parameters"][0]["value"]
+            .as_f64()
+            .unwrap()
+    };
+    let (micro_jy, jy) = (amplitude("uJy"), amplitude("Jy"));
+    assert!((micro_jy / 1000.0 - 1.0).abs() < 0.01, "{}", micro_jy);
+    assert!(
+        (jy / micro_jy / 1e-6 - 1.0).abs() < 0.01,
+        "{} {}",
+        jy,
+        micro_jy
+    );
+
+    let mag_amplitude = |unit| {
+        request("/api/v0.5/", unit)["bazin_fit_mag_full_amplitude_flux"]
+            .as_f64()
+            .unwrap()
+    };
+    assert!((mag_amplitude("uJy") - mag_amplitude("Jy")).abs() < 0.01);
+}
+
+/// Check that valid curve-fit settings are used and invalid ones are rejected
+#[test]
+fn curve_fit() {
+    use rocket::http::Status;
+    use rocket::serde::json::Value;
+
+    let client = Client::tracked(super::rocket()).unwrap();
+    let request = |path: &'static str, curve_fit: &str| {
+        client
+            .post(path)
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"curve_fit": {}, {}"#,
+                curve_fit,
+                &light_curve_json(20)[1..]
+            ))
+            .dispatch()
+    };
+
+    let fit = request("/api/v0.5/fit/bazin", r#"{"algorithm": "lmsder"}"#)
+        .into_json::<Value>()
+        .unwrap();
+    assert_eq!(fit["curve_fit"]["algorithm"], "lmsder");
+    assert_eq!(fit["curve_fit"]["niterations"], 10);
+    for curve_fit in [
+        r#"{"algorithm": "ceres", "niterations": 5, "loss_factor": 1.0}"#,
+        r#"{"algorithm": "mcmc", "niterations": 16, "fine_tuning": {"algorithm": "lmsder"}}"#,
+        r#"{"algorithm": "robust", "mcmc_niterations": 16}"#,
+    ] {
+        assert_eq!(
+            request("/api/v0.5/", curve_fit).status(),
+            Status::Ok,
+            "{}",
+            curve_fit
+        );
+    }
+
+    for curve_fit in [
+        r#"{"algorithm": "ceres", "niterations": 0}"#,
+        r#"{"algorithm": "ceres", "loss_factor": -1.0}"#,
+        r#"{"algorithm": "mcmc", "fine_tuning": {"algorithm": "lmsder", "niterations": 0}}"#,
+    ] {
+        assert_eq!(
+            request("/api/v0.5/", curve_fit).status(),
+            Status::BadRequest,
+            "{}",
+            curve_fit
+        );
     }
+    assert_eq!(
+        request("/api/v0.5/", r#"{"algorithm": "newton"}"#).status(),
+        Status::UnprocessableEntity
+    );
 }
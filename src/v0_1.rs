@@ -1,10 +1,15 @@
 use lazy_static::lazy_static;
 use light_curve_feature_0_1::*;
 use rocket::response::status::BadRequest;
-use rocket::serde::{json::Json, Deserialize};
+use rocket::serde::{
+    json::{serde_json, Json, Value},
+    Deserialize,
+};
 use std::collections::HashMap;
 use unzip3::Unzip3;
 
+use crate::registry::{ApiVersion, Deprecation};
+
 #[derive(Debug, Deserialize)]
 pub struct Data {
     light_curve: Vec<Observation>,
@@ -111,3 +116,45 @@ pub fn index(mut data: Json<Data>) -> Result<Json<FeatureValues>, BadRequest<&'s
         .collect();
     Ok(Json(features))
 }
+
+pub struct Version;
+
+impl ApiVersion for Version {
+    fn name(&self) -> &'static str {
+        "v0.1"
+    }
+
+    fn full_version(&self) -> &'static str {
+        "0.1.17"
+    }
+
+    fn description(&self) -> &'static str {
+        "Magnitude features of light-curve-feature v0.1, periodogram peaks are searched for periods longer than five minutes"
+    }
+
+    fn routes(&self) -> Vec<rocket::Route> {
+        routes![index]
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        FE.get_names().into_iter().map(String::from).collect()
+    }
+
+    fn backends(&self) -> &'static [&'static str] {
+        &["fftw-source"]
+    }
+
+    fn eval(&self, data: Value) -> Result<HashMap<String, f64>, String> {
+        let data: Data = serde_json::from_value(data).map_err(|e| e.to_string())?;
+        index(Json(data))
+            .map(|features| features.0)
+            .map_err(|BadRequest(e)| e.map(String::from).unwrap_or_default())
+    }
+
+    fn deprecation(&self) -> Option<Deprecation> {
+        Some(Deprecation {
+            sunset: None,
+            successor: "v0.5".into(),
+        })
+    }
+}
@@ -1,9 +1,14 @@
 use light_curve_feature_0_4::*;
 use ndarray::{Array1, Zip};
 use rocket::response::status::BadRequest;
-use rocket::serde::{json::Json, Deserialize};
+use rocket::serde::{
+    json::{serde_json, Json, Value},
+    Deserialize,
+};
 use std::collections::HashMap;
 
+use crate::registry::ApiVersion;
+
 #[derive(Debug, Deserialize)]
 pub struct Data {
     light_curve: Vec<Observation>,
@@ -139,3 +144,38 @@ pub fn index(mut data: Json<Data>) -> Result<Json<FeatureValues>, BadRequest<Str
         FEATURE_NAMES.with(|names| names.iter().cloned().zip(values.into_iter()).collect());
     Ok(Json(features))
 }
+
+pub struct Version;
+
+impl ApiVersion for Version {
+    fn name(&self) -> &'static str {
+        "v0.4"
+    }
+
+    fn full_version(&self) -> &'static str {
+        "0.4.5"
+    }
+
+    fn description(&self) -> &'static str {
+        "Magnitude and flux features of light-curve-feature v0.4"
+    }
+
+    fn routes(&self) -> Vec<rocket::Route> {
+        routes![index]
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        FEATURE_NAMES.with(|names| names.clone())
+    }
+
+    fn backends(&self) -> &'static [&'static str] {
+        &["fftw-source"]
+    }
+
+    fn eval(&self, data: Value) -> Result<HashMap<String, f64>, String> {
+        let data: Data = serde_json::from_value(data).map_err(|e| e.to_string())?;
+        index(Json(data))
+            .map(|features| features.0)
+            .map_err(|BadRequest(e)| e.unwrap_or_default())
+    }
+}
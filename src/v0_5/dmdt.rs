@@ -0,0 +1,172 @@
+use light_curve_dmdt::{DmDt, ExactErf, LgGrid, LinearGrid};
+use light_curve_feature_0_5::ndarray::Array2;
+use rocket::http::Header;
+use rocket::response::status::BadRequest;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+
+use super::{preprocessed_time_series, Data};
+use crate::access_log::{ObjectId, RequestLog};
+use crate::json_input::JsonInput;
+
+/// Maximum number of cells of the dm–dt map
+const MAX_MAP_SIZE: usize = 1 << 20;
+
+/// Grid of the map axis, e.g. `{"scale": "log", "start": 1.0, "end": 1000.0, "size": 32}`
+///
+/// Logarithmic grid requires positive `start`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(tag = "scale", rename_all = "lowercase")]
+pub enum GridSpec {
+    Linear { start: f64, end: f64, size: usize },
+    Log { start: f64, end: f64, size: usize },
+}
+
+impl GridSpec {
+    fn size(self) -> usize {
+        match self {
+            Self::Linear { size, .. } | Self::Log { size, .. } => size,
+        }
+    }
+
+    fn validate(self) -> Result<(), BadRequest<String>> {
+        let valid = match self {
+            Self::Linear { start, end, size } => start.is_finite() && end > start && size > 0,
+            Self::Log { start, end, size } => {
+                start.is_finite() && start > 0.0 && end > start && size > 0
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(BadRequest(Some(
+                "Bad request: grid must have finite start less than its end and positive size, logarithmic grid must have positive start".into(),
+            )))
+        }
+    }
+
+    /// Cell borders, `size + 1` values
+    fn borders(self) -> Vec<f64> {
+        match self {
+            Self::Linear { start, end, size } => (0..=size)
+                .map(|i| start + (end - start) * i as f64 / size as f64)
+                .collect(),
+            Self::Log { start, end, size } => {
+                let (lg_start, lg_end) = (start.log10(), end.log10());
+                (0..=size)
+                    .map(|i| 10_f64.powf(lg_start + (lg_end - lg_start) * i as f64 / size as f64))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// dm–dt map normalization
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalization {
+    /// Number of observation pairs in the cell
+    #[default]
+    Counts,
+    /// Fraction of observation pairs in the cell
+    Probability,
+    /// Pairs smeared along dm with Gaussians of their combined errors
+    Gaussian,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DmdtData {
+    #[serde(flatten)]
+    data: Data,
+    dt: GridSpec,
+    dm: GridSpec,
+    #[serde(default)]
+    normalization: Normalization,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DmdtResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    dt_borders: Vec<f64>,
+    dm_borders: Vec<f64>,
+    /// Map of `dt` rows and `dm` columns
+    map: Vec<Vec<f64>>,
+}
+
+#[derive(Responder)]
+pub enum DmdtOutput {
+    Json(Json<DmdtResponse>),
+    /// Row-major little-endian `f64` array, its shape is in the `X-Dmdt-Shape` header
+    #[response(content_type = "binary")]
+    Binary(Vec<u8>, Header<'static>),
+}
+
+fn map(dmdt_data: DmdtData) -> Result<Array2<f64>, BadRequest<String>> {
+    let Data {
+        light_curve,
+        preprocessing,
+        ..
+    } = dmdt_data.data;
+    let (ts, _removed_indices) = preprocessed_time_series(light_curve, &preprocessing)?;
+    let t = ts.t.sample.as_slice().unwrap();
+    let m = ts.m.sample.as_slice().unwrap();
+
+    let dmdt = DmDt::from_grids(grid(dmdt_data.dt), grid(dmdt_data.dm));
+    let map = match dmdt_data.normalization {
+        Normalization::Counts => dmdt.points(t, m).mapv(|count| count as f64),
+        Normalization::Probability => {
+            let n_pairs = (t.len() * (t.len() - 1) / 2) as f64;
+            dmdt.points(t, m).mapv(|count| count as f64 / n_pairs)
+        }
+        Normalization::Gaussian => {
+            let err2: Vec<f64> = ts.w.sample.iter().map(|w| w.recip()).collect();
+            dmdt.gausses::<ExactErf>(t, m, &err2)
+        }
+    };
+    Ok(map)
+}
+
+fn grid(spec: GridSpec) -> light_curve_dmdt::Grid<f64> {
+    match spec {
+        GridSpec::Linear { start, end, size } => LinearGrid::new(start, end, size).into(),
+        GridSpec::Log { start, end, size } => LgGrid::from_start_end(start, end, size).into(),
+    }
+}
+
+/// 2D histogram of magnitude and time differences of all observation pairs
+///
+/// Response is JSON by default or a binary array with `?binary=true`, the latter doesn't echo `id`
+#[post("/dmdt?<binary>", format = "application/json", data = "<dmdt_data>")]
+pub fn dmdt(
+    dmdt_data: JsonInput<DmdtData>,
+    binary: Option<bool>,
+    log: &RequestLog,
+) -> Result<DmdtOutput, BadRequest<String>> {
+    let mut dmdt_data = dmdt_data.0;
+    let id = dmdt_data.data.id.take();
+    log.object(&id, dmdt_data.data.light_curve.len());
+    dmdt_data.dt.validate()?;
+    dmdt_data.dm.validate()?;
+    let (dt, dm) = (dmdt_data.dt, dmdt_data.dm);
+    if dt.size().saturating_mul(dm.size()) > MAX_MAP_SIZE {
+        return Err(BadRequest(Some(format!(
+            "Bad request: dm-dt map must have at most {} cells",
+            MAX_MAP_SIZE
+        ))));
+    }
+
+    let map = map(dmdt_data)?;
+
+    if binary.unwrap_or(false) {
+        let bytes = map.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let shape = Header::new("X-Dmdt-Shape", format!("{},{}", dt.size(), dm.size()));
+        Ok(DmdtOutput::Binary(bytes, shape))
+    } else {
+        Ok(DmdtOutput::Json(Json(DmdtResponse {
+            id,
+            dt_borders: dt.borders(),
+            dm_borders: dm.borders(),
+            map: map.outer_iter().map(|row| row.to_vec()).collect(),
+        })))
+    }
+}
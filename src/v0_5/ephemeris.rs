@@ -0,0 +1,183 @@
+use rocket::serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Speed of light, AU per day
+const SPEED_OF_LIGHT: f64 = 173.144_632_674;
+
+/// Earth equatorial radius, AU
+const EARTH_RADIUS: f64 = 4.263_523e-5;
+
+/// Obliquity of the ecliptic at J2000, degrees
+const OBLIQUITY_J2000: f64 = 23.439_291_1;
+
+/// MJD of J2000 epoch
+const J2000_MJD: f64 = 51544.5;
+
+/// Planet mass to the Sun mass ratio, semi-major axis in AU, J2000 mean longitude and its rate per
+/// Julian century in degrees
+const GIANT_PLANETS: [(f64, f64, f64, f64); 2] = [
+    (1.0 / 1_047.348_6, 5.202_887, 34.396_44, 3_034.746_128), // Jupiter
+    (1.0 / 3_497.898, 9.536_676, 49.954_24, 1_222.493_622),   // Saturn
+];
+
+/// Observatory codes of the Minor Planet Center: longitude and latitude in degrees
+const OBSERVATORIES: [(&str, f64, f64); 7] = [
+    ("500", 0.0, 0.0), // geocenter, handled separately
+    ("I41", -116.8599, 33.3564),
+    ("X05", -70.7494, -30.2446),
+    ("F51", -156.2569, 20.7083),
+    ("T08", -155.5761, 19.5362),
+    ("695", -111.6003, 31.9633),
+    ("807", -70.8150, -30.1650),
+];
+
+/// Reference point of the light-travel time correction
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Reference {
+    /// Solar system barycenter, BJD
+    #[default]
+    Barycentric,
+    /// Sun center, HJD
+    Heliocentric,
+}
+
+/// Range of the applied corrections, in days
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TimeCorrectionRange {
+    pub reference: Reference,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Light-travel time correction to the Sun or the solar system barycenter, e.g.
+/// `{"ra": 83.63, "dec": 22.01, "observatory": "I41"}`
+///
+/// Coordinates are ICRS degrees, observatory is the Minor Planet Center code, geocenter by default.
+/// Positions are computed analytically: the Earth from low-precision solar coordinates and the
+/// barycenter from Jupiter and Saturn mean circular orbits, so the accuracy is about a few seconds.
+/// Time scale is not changed
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TimeCorrection {
+    ra: f64,
+    dec: f64,
+    #[serde(default)]
+    observatory: Option<String>,
+    #[serde(default)]
+    reference: Reference,
+}
+
+impl TimeCorrection {
+    /// Observatory longitude and latitude in radians, `None` for the geocenter
+    fn observatory(&self) -> Result<Option<(f64, f64)>, String> {
+        match self.observatory.as_deref() {
+            None | Some("500") => Ok(None),
+            Some(code) => OBSERVATORIES
+                .iter()
+                .find(|(c, _, _)| *c == code)
+                .map(|&(_, lon, lat)| Some((lon.to_radians(), lat.to_radians())))
+                .ok_or_else(|| {
+                    let codes: Vec<_> = OBSERVATORIES.iter().map(|(c, _, _)| *c).collect();
+                    format!(
+                        "unknown observatory code {:?}, known codes are {}",
+                        code,
+                        codes.join(", ")
+                    )
+                }),
+        }
+    }
+
+    /// Correct MJD times in place, returns the range of corrections
+    pub fn apply(&self, mjd: &mut [f64]) -> Result<TimeCorrectionRange, String> {
+        if !(0.0..=360.0).contains(&self.ra) || !(-90.0..=90.0).contains(&self.dec) {
+            return Err("right ascension must be from 0 to 360 and declination must be from -90 to 90 degrees".into());
+        }
+        let observatory = self.observatory()?;
+        let (ra, dec) = (self.ra.to_radians(), self.dec.to_radians());
+        let source = [dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin()];
+
+        let mut range = TimeCorrectionRange {
+            reference: self.reference,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        };
+        for t in mjd.iter_mut() {
+            let mut position = earth_heliocentric(*t);
+            if self.reference == Reference::Barycentric {
+                let sun = sun_barycentric(*t);
+                for (p, s) in position.iter_mut().zip(sun) {
+                    *p += s;
+                }
+            }
+            if let Some((lon, lat)) = observatory {
+                let topocentric = observatory_geocentric(*t, lon, lat);
+                for (p, o) in position.iter_mut().zip(topocentric) {
+                    *p += o;
+                }
+            }
+            let correction = dot(position, source) / SPEED_OF_LIGHT;
+            range.min = range.min.min(correction);
+            range.max = range.max.max(correction);
+            *t += correction;
+        }
+        Ok(range)
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Ecliptic to equatorial J2000 coordinates
+fn ecliptic_to_equatorial(longitude: f64, latitude: f64, distance: f64) -> [f64; 3] {
+    let eps = OBLIQUITY_J2000.to_radians();
+    let (x, y, z) = (
+        distance * latitude.cos() * longitude.cos(),
+        distance * latitude.cos() * longitude.sin(),
+        distance * latitude.sin(),
+    );
+    [
+        x,
+        y * eps.cos() - z * eps.sin(),
+        y * eps.sin() + z * eps.cos(),
+    ]
+}
+
+/// Heliocentric equatorial position of the Earth in AU, Astronomical Almanac low-precision solar
+/// coordinates referred to the J2000 equinox
+pub(crate) fn earth_heliocentric(mjd: f64) -> [f64; 3] {
+    let n = mjd - J2000_MJD;
+    let mean_longitude = 280.460 + 0.985_647_4 * n;
+    let g = (357.528 + 0.985_600_3 * n).to_radians();
+    // Precession from the equinox of date to J2000
+    let precession = 1.396_971 * n / 36525.0;
+    let longitude = mean_longitude + 1.915 * g.sin() + 0.020 * (2.0 * g).sin() - precession;
+    let distance = 1.000_14 - 0.016_71 * g.cos() - 0.000_14 * (2.0 * g).cos();
+    // The Earth is opposite to the geocentric Sun
+    ecliptic_to_equatorial(longitude.to_radians() + PI, 0.0, distance)
+}
+
+/// Position of the Sun relative to the solar system barycenter in AU
+fn sun_barycentric(mjd: f64) -> [f64; 3] {
+    let centuries = (mjd - J2000_MJD) / 36525.0;
+    GIANT_PLANETS
+        .iter()
+        .map(|&(mass_ratio, a, longitude, rate)| {
+            let longitude = (longitude + rate * centuries).to_radians();
+            ecliptic_to_equatorial(longitude, 0.0, -mass_ratio * a)
+        })
+        .fold([0.0; 3], |acc, p| {
+            [acc[0] + p[0], acc[1] + p[1], acc[2] + p[2]]
+        })
+}
+
+/// Geocentric equatorial position of an observatory on the spherical Earth in AU
+fn observatory_geocentric(mjd: f64, lon: f64, lat: f64) -> [f64; 3] {
+    let gmst = (280.460_618_37 + 360.985_647_366_29 * (mjd - J2000_MJD)).to_radians();
+    let hour_angle = gmst + lon;
+    [
+        EARTH_RADIUS * lat.cos() * hour_angle.cos(),
+        EARTH_RADIUS * lat.cos() * hour_angle.sin(),
+        EARTH_RADIUS * lat.sin(),
+    ]
+}
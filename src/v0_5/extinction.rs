@@ -0,0 +1,195 @@
+use lazy_static::lazy_static;
+use rocket::serde::{Deserialize, Serialize};
+use std::fs;
+
+use super::Observation;
+
+/// Environment variable with the path of the dust map file overriding the bundled map
+const DUST_MAP_ENV: &str = "DUST_MAP_PATH";
+
+/// Downsampled SFD map embedded at build time from `dust_map.txt` produced by `dust_map.py`, empty
+/// if the file was missing
+const BUNDLED_DUST_MAP: &str = include_str!(concat!(env!("OUT_DIR"), "/dust_map.txt"));
+
+/// Extinction to E(B−V) ratios for R_V = 3.1 (Schlafly & Finkbeiner 2011)
+const EXTINCTION_COEFFICIENTS: [(&str, f64); 23] = [
+    ("B", 3.626),
+    ("V", 2.742),
+    ("R", 2.169),
+    ("I", 1.505),
+    ("u", 4.239),
+    ("g", 3.303),
+    ("r", 2.285),
+    ("i", 1.698),
+    ("z", 1.263),
+    ("ps1_g", 3.172),
+    ("ps1_r", 2.271),
+    ("ps1_i", 1.682),
+    ("ps1_z", 1.322),
+    ("ps1_y", 1.087),
+    ("lsst_u", 4.145),
+    ("lsst_g", 3.237),
+    ("lsst_r", 2.273),
+    ("lsst_i", 1.684),
+    ("lsst_z", 1.323),
+    ("lsst_y", 1.088),
+    ("J", 0.709),
+    ("H", 0.449),
+    ("Ks", 0.302),
+];
+
+/// ICRS to Galactic rotation matrix
+const ICRS_TO_GALACTIC: [[f64; 3]; 3] = [
+    [-0.054_875_539_390, -0.873_437_104_725, -0.483_834_991_775],
+    [0.494_109_453_633, -0.444_829_594_298, 0.746_982_248_696],
+    [-0.867_666_135_681, -0.198_076_389_622, 0.455_983_794_523],
+];
+
+lazy_static! {
+    static ref DUST_MAP: Result<DustMap, String> = DustMap::load();
+}
+
+/// E(B−V) map on the equirectangular grid of Galactic coordinates
+///
+/// The text file starts with the numbers of longitude and latitude nodes, followed by E(B−V)
+/// values in rows of constant latitude from −90° to 90°, longitudes of a row go from 0° to 360°
+/// exclusive
+struct DustMap {
+    n_l: usize,
+    n_b: usize,
+    ebv: Vec<f64>,
+}
+
+impl DustMap {
+    /// Map of the `DUST_MAP_PATH` file if the variable is set, the bundled map otherwise
+    fn load() -> Result<Self, String> {
+        match std::env::var(DUST_MAP_ENV) {
+            Ok(path) => {
+                let text = fs::read_to_string(&path)
+                    .map_err(|e| format!("cannot read dust map {:?}: {}", path, e))?;
+                Self::parse(&text)
+            }
+            Err(_) if !BUNDLED_DUST_MAP.is_empty() => Self::parse(BUNDLED_DUST_MAP),
+            Err(_) => Err(format!(
+                "dust map is not bundled, set {} environment variable or give ebv",
+                DUST_MAP_ENV
+            )),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut values = text.split_whitespace();
+        let mut size = || -> Result<usize, String> {
+            values
+                .next()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| "dust map must start with its grid size".to_owned())
+        };
+        let (n_l, n_b) = (size()?, size()?);
+        let ebv = values
+            .map(|s| s.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid dust map value: {}", e))?;
+        if n_l == 0 || n_b < 2 || ebv.len() != n_l * n_b {
+            return Err(format!("dust map must have {} × {} values", n_l, n_b));
+        }
+        Ok(Self { n_l, n_b, ebv })
+    }
+
+    /// E(B−V) of the nearest grid node
+    fn ebv(&self, l: f64, b: f64) -> f64 {
+        let i_l = (l / 360.0 * self.n_l as f64).round() as usize % self.n_l;
+        let i_b = ((b + 90.0) / 180.0 * (self.n_b - 1) as f64).round() as usize;
+        self.ebv[i_b * self.n_l + i_l]
+    }
+}
+
+/// Galactic longitude and latitude in degrees of ICRS coordinates in degrees
+fn galactic(ra: f64, dec: f64) -> (f64, f64) {
+    let (ra, dec) = (ra.to_radians(), dec.to_radians());
+    let icrs = [dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin()];
+    let [x, y, z] =
+        ICRS_TO_GALACTIC.map(|row| row[0] * icrs[0] + row[1] * icrs[1] + row[2] * icrs[2]);
+    (
+        f64::atan2(y, x).to_degrees().rem_euclid(360.0),
+        z.clamp(-1.0, 1.0).asin().to_degrees(),
+    )
+}
+
+fn extinction_coefficient(band: &str) -> Result<f64, String> {
+    EXTINCTION_COEFFICIENTS
+        .iter()
+        .find(|(name, _)| *name == band)
+        .map(|&(_, coefficient)| coefficient)
+        .ok_or_else(|| {
+            let names: Vec<_> = EXTINCTION_COEFFICIENTS
+                .iter()
+                .map(|(name, _)| *name)
+                .collect();
+            format!(
+                "unknown passband {:?} for extinction correction, known passbands are {}",
+                band,
+                names.join(", ")
+            )
+        })
+}
+
+/// Source of the color excess
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ColorExcess {
+    Value {
+        ebv: f64,
+    },
+    /// ICRS coordinates in degrees to look up in the dust map
+    Coordinates {
+        ra: f64,
+        dec: f64,
+    },
+}
+
+/// Galactic extinction correction, e.g. `{"ebv": 0.05, "band": "r"}` or
+/// `{"ra": 83.63, "dec": 22.01}`
+///
+/// Passband defaults to the `band` of each observation
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Extinction {
+    #[serde(flatten)]
+    color_excess: ColorExcess,
+    #[serde(default)]
+    band: Option<String>,
+}
+
+impl Extinction {
+    fn ebv(&self) -> Result<f64, String> {
+        let ebv = match self.color_excess {
+            ColorExcess::Value { ebv } => ebv,
+            ColorExcess::Coordinates { ra, dec } => {
+                if !(0.0..=360.0).contains(&ra) || !(-90.0..=90.0).contains(&dec) {
+                    return Err("right ascension must be from 0 to 360 and declination must be from -90 to 90 degrees".into());
+                }
+                let (l, b) = galactic(ra, dec);
+                DUST_MAP.as_ref().map_err(|e| e.clone())?.ebv(l, b)
+            }
+        };
+        if ebv.is_finite() && ebv >= 0.0 {
+            Ok(ebv)
+        } else {
+            Err("E(B-V) must be non-negative".into())
+        }
+    }
+
+    /// Subtract extinction from magnitudes
+    pub fn apply(&self, light_curve: &mut [Observation]) -> Result<(), String> {
+        let ebv = self.ebv()?;
+        for obs in light_curve.iter_mut() {
+            let band = self
+                .band
+                .as_deref()
+                .or(obs.band.as_deref())
+                .ok_or("extinction correction requires passband of the light curve or of every observation")?;
+            obs.m -= extinction_coefficient(band)? * ebv;
+        }
+        Ok(())
+    }
+}
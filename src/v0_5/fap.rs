@@ -0,0 +1,132 @@
+use light_curve_feature_0_5::ndarray::Array1;
+use light_curve_feature_0_5::*;
+use rand::Rng;
+use rocket::response::status::BadRequest;
+use rocket::serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use crate::hash::fnv1a;
+
+/// Maximum number of bootstrap iterations
+const MAX_BOOTSTRAP_ITERATIONS: usize = 1000;
+
+/// False-alarm probability estimation method, e.g. `{"method": "baluev"}`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "method", rename_all = "lowercase")]
+pub enum FapMethod {
+    /// Analytic approximation of Baluev (2008)
+    Baluev,
+    /// Fraction of light curves with resampled magnitudes having higher periodogram maximum
+    Bootstrap {
+        #[serde(default = "FapMethod::default_bootstrap_iterations")]
+        niterations: usize,
+    },
+}
+
+impl FapMethod {
+    fn default_bootstrap_iterations() -> usize {
+        100
+    }
+
+    pub fn validate(&self) -> Result<(), BadRequest<String>> {
+        match self {
+            Self::Baluev => Ok(()),
+            Self::Bootstrap { niterations } => {
+                if (1..=MAX_BOOTSTRAP_ITERATIONS).contains(niterations) {
+                    Ok(())
+                } else {
+                    Err(BadRequest(Some(format!(
+                        "Bad request: number of bootstrap iterations must be from 1 to {}",
+                        MAX_BOOTSTRAP_ITERATIONS
+                    ))))
+                }
+            }
+        }
+    }
+}
+
+/// Power of the grid point nearest to the period, `freq` is the ascending angular frequency grid
+pub fn peak_power(freq: &[f64], power: &[f64], period: f64) -> f64 {
+    let omega = 2.0 * PI / period;
+    let i = freq.partition_point(|&f| f < omega);
+    match (i.checked_sub(1), freq.get(i)) {
+        (Some(left), Some(&right)) if omega - freq[left] < right - omega => power[left],
+        (_, Some(_)) => power[i],
+        (Some(left), None) => power[left],
+        (None, None) => f64::NAN,
+    }
+}
+
+/// Natural logarithm of the gamma function for `x > 0`, Stirling series after upward recurrence
+fn ln_gamma(x: f64) -> f64 {
+    let shift = (10.0 - x).max(0.0).ceil();
+    let correction: f64 = (0..shift as usize).map(|i| f64::ln(x + i as f64)).sum();
+    let x = x + shift;
+    (x - 0.5) * x.ln() - x + 0.5 * f64::ln(2.0 * PI) + 1.0 / (12.0 * x) - 1.0 / (360.0 * x.powi(3))
+        + 1.0 / (1260.0 * x.powi(5))
+        - correction
+}
+
+/// Baluev (2008) false-alarm probability of a periodogram peak
+///
+/// `power` is the `light-curve-feature` periodogram power normalized by the magnitude variance,
+/// `max_freq` is the maximum angular frequency of the grid
+pub fn baluev(power: f64, t: &[f64], max_freq: f64) -> f64 {
+    let n = t.len() as f64;
+    // Degrees of freedom of the constant and the sinusoidal models
+    let n_h = n - 1.0;
+    let n_k = n - 3.0;
+    // Standard normalization, z in [0, 1]
+    let z = f64::clamp(2.0 * power / n_h, 0.0, 1.0);
+
+    let t_mean = t.iter().sum::<f64>() / n;
+    let t_var = t.iter().map(|&t| (t - t_mean).powi(2)).sum::<f64>() / n;
+    let effective_duration = f64::sqrt(4.0 * PI * t_var);
+    let w = max_freq / (2.0 * PI) * effective_duration;
+    let gamma = f64::sqrt(2.0 / n_h) * f64::exp(ln_gamma(0.5 * n_h) - ln_gamma(0.5 * (n_h - 1.0)));
+    let tau = gamma * w * (1.0 - z).powf(0.5 * (n_k - 1.0)) * f64::sqrt(0.5 * n_h * z);
+    // Probability that a single-frequency power is below z
+    let cdf_single = 1.0 - (1.0 - z).powf(0.5 * n_k);
+    f64::clamp(1.0 - cdf_single * f64::exp(-tau), 0.0, 1.0)
+}
+
+/// Periodogram maxima of light curves with magnitudes resampled with replacement
+pub fn bootstrap_max_powers(
+    ts: &TimeSeries<f64>,
+    periodogram: &Periodogram<f64, Feature<f64>>,
+    niterations: usize,
+    rng: &mut impl Rng,
+) -> Vec<f64> {
+    let n = ts.lenu();
+    (0..niterations)
+        .map(|_| {
+            let indices: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+            let m = Array1::from_shape_fn(n, |i| ts.m.sample[indices[i]]);
+            let w = Array1::from_shape_fn(n, |i| ts.w.sample[indices[i]]);
+            let mut resampled = TimeSeries::new(ts.t.sample.view(), m, w);
+            let (_freq, power) = periodogram.freq_power(&mut resampled);
+            power.into_iter().fold(f64::NEG_INFINITY, f64::max)
+        })
+        .collect()
+}
+
+/// Default random seed derived from the time series, so the resampling is reproducible
+pub fn light_curve_seed(ts: &TimeSeries<f64>) -> u64 {
+    let bytes: Vec<u8> =
+        ts.t.sample
+            .iter()
+            .chain(ts.m.sample.iter())
+            .chain(ts.w.sample.iter())
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+    fnv1a(&bytes)
+}
+
+/// Fraction of bootstrap maxima exceeding the peak power
+pub fn bootstrap(power: f64, max_powers: &[f64]) -> f64 {
+    max_powers
+        .iter()
+        .filter(|&&max_power| max_power >= power)
+        .count() as f64
+        / max_powers.len() as f64
+}
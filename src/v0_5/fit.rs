@@ -0,0 +1,717 @@
+use light_curve_feature_0_5::ndarray::{Array1, Array2, ArrayView1};
+use light_curve_feature_0_5::prelude::FitFunctionTrait;
+use light_curve_feature_0_5::transformers::{
+    bazin_fit::BazinFitTransformer, villar_fit::VillarFitTransformer,
+};
+use light_curve_feature_0_5::*;
+use rocket::request::FromParam;
+use rocket::response::status::BadRequest;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use std::convert::TryInto;
+use std::time::Instant;
+
+use super::limits::{upper_limits, UPPER_LIMIT_SNR};
+use super::linalg::invert;
+use super::{
+    extractor_hash, flux_ts_from_mag_ts, preprocessed_time_series, CurveFit, Data,
+    LIGHT_CURVE_FEATURE_VERSION,
+};
+use crate::access_log::{ObjectId, RequestLog};
+use crate::json_input::JsonInput;
+use crate::meta::Meta;
+
+/// Maximum number of points of the model light curve
+const MAX_T_GRID_SIZE: usize = 100_000;
+
+/// Default number of points of the model light curve
+const DEFAULT_T_GRID_SIZE: usize = 100;
+
+/// Maximum number of Levenberg–Marquardt iterations of the fit with upper limits
+const CENSORED_FIT_ITERATIONS: usize = 100;
+
+/// Parametric model of the flux light curve
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FitModel {
+    Bazin,
+    Villar,
+    /// `B + A (t − t₀)/τ exp(−(t − t₀)/τ)`
+    Linexp,
+    /// `B + A (t − t₀)^α` after `t₀` and `B` before it
+    PowerLaw,
+}
+
+/// Fits provided by `light-curve-feature`
+pub const EXTRACTOR_MODELS: [FitModel; 2] = [FitModel::Bazin, FitModel::Villar];
+
+/// Fits missed in `light-curve-feature` v0.5, they are fitted with Levenberg–Marquardt iterations
+pub const IN_HOUSE_MODELS: [FitModel; 2] = [FitModel::Linexp, FitModel::PowerLaw];
+
+impl<'a> FromParam<'a> for FitModel {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        match param {
+            "bazin" => Ok(Self::Bazin),
+            "villar" => Ok(Self::Villar),
+            "linexp" => Ok(Self::Linexp),
+            "powerlaw" => Ok(Self::PowerLaw),
+            _ => Err(param),
+        }
+    }
+}
+
+impl FitModel {
+    /// Lower and upper parameter bounds, amplitude bounds correspond to magnitudes of 30 and 0
+    fn bounds(self, zp: f64) -> Vec<(Option<f64>, Option<f64>)> {
+        let amplitude = (Some(mag_to_flux(30.0, zp)), Some(mag_to_flux(0.0, zp)));
+        let time_scale = (Some(1e-4), Some(3e4));
+        match self {
+            Self::Bazin => vec![
+                amplitude,    // amplitude
+                (None, None), // baseline
+                (None, None), // t0
+                time_scale,   // rise time
+                time_scale,   // fall time
+            ],
+            Self::Villar => vec![
+                amplitude,              // amplitude
+                (None, None),           // baseline
+                (None, None),           // t0
+                time_scale,             // rise time
+                time_scale,             // fall time
+                (Some(0.0), Some(1.0)), // plateau relative amplitude
+                (Some(0.0), Some(3e4)), // plateau duration
+            ],
+            Self::Linexp => vec![
+                amplitude,    // amplitude
+                (None, None), // baseline
+                (None, None), // t0
+                time_scale,   // rise time
+            ],
+            Self::PowerLaw => vec![
+                amplitude,               // amplitude, flux a day after t0
+                (None, None),            // baseline
+                (None, None),            // t0
+                (Some(0.1), Some(10.0)), // index
+            ],
+        }
+    }
+
+    /// Fit evaluator with amplitude bounds corresponding to magnitudes of 30 and 0, `None` for the
+    /// in-house fits
+    fn evaluator(self, zp: f64, curve_fit: &CurveFit) -> Option<Feature<f64>> {
+        let (lower, upper): (Vec<_>, Vec<_>) = self.bounds(zp).into_iter().unzip();
+        match self {
+            Self::Bazin => {
+                let inits_bounds = BazinInitsBounds::option_arrays(
+                    [None; 5],
+                    lower.try_into().unwrap(),
+                    upper.try_into().unwrap(),
+                );
+                Some(BazinFit::new(curve_fit.algorithm(), LnPrior::none(), inits_bounds).into())
+            }
+            Self::Villar => {
+                let inits_bounds = VillarInitsBounds::option_arrays(
+                    [None; 7],
+                    lower.try_into().unwrap(),
+                    upper.try_into().unwrap(),
+                );
+                Some(VillarFit::new(curve_fit.algorithm(), LnPrior::none(), inits_bounds).into())
+            }
+            Self::Linexp | Self::PowerLaw => None,
+        }
+    }
+
+    /// Names of the fit parameters
+    fn parameter_names(self) -> &'static [&'static str] {
+        match self {
+            Self::Bazin => &[
+                "amplitude",
+                "baseline",
+                "reference_time",
+                "rise_time",
+                "fall_time",
+            ],
+            Self::Villar => &[
+                "amplitude",
+                "baseline",
+                "reference_time",
+                "rise_time",
+                "fall_time",
+                "plateau_rel_amplitude",
+                "plateau_duration",
+            ],
+            Self::Linexp => &["amplitude", "baseline", "reference_time", "rise_time"],
+            Self::PowerLaw => &["amplitude", "baseline", "reference_time", "index"],
+        }
+    }
+
+    /// Initial parameters of the in-house fits: the baseline is the minimum flux and the model
+    /// reaches the brightest detection
+    fn initial_params(self, flux_ts: &TimeSeries<f64>) -> Vec<f64> {
+        let t = &flux_ts.t.sample;
+        let flux = &flux_ts.m.sample;
+        let i_peak = (0..flux.len())
+            .max_by(|&a, &b| flux[a].total_cmp(&flux[b]))
+            .unwrap();
+        let (t_first, t_last, t_peak) = (t[0], t[t.len() - 1], t[i_peak]);
+        let baseline = flux.iter().copied().fold(f64::INFINITY, f64::min);
+        // At least a day, so the initial amplitudes are finite
+        let rise_time = f64::max(t_peak - t_first, 0.1 * (t_last - t_first)).max(1.0);
+        match self {
+            Self::Linexp => vec![
+                std::f64::consts::E * (flux[i_peak] - baseline),
+                baseline,
+                t_peak - rise_time,
+                rise_time,
+            ],
+            Self::PowerLaw => vec![
+                (flux[i_peak] - baseline) / rise_time,
+                baseline,
+                t_peak - rise_time,
+                1.0,
+            ],
+            Self::Bazin | Self::Villar => {
+                unreachable!("only in-house fits need initial parameters")
+            }
+        }
+    }
+
+    /// Unconstrained parameters the fits of `light-curve-feature` optimize: the plateau relative
+    /// amplitude `ν` of the Villar fit is replaced with `atanh(ν)`
+    fn to_internal(self, params: &[f64]) -> Vec<f64> {
+        let mut internal = params.to_vec();
+        if let Self::Villar = self {
+            internal[5] = f64::min(params[5], 1.0 - f64::EPSILON).atanh();
+        }
+        internal
+    }
+
+    /// Parameters of unconstrained ones, the same as `internal_to_dimensionless` of
+    /// `light-curve-feature`: amplitude and time-scales are taken by absolute value
+    fn to_external(self, internal: &[f64]) -> Vec<f64> {
+        let mut params = internal.to_vec();
+        let positive: &[usize] = match self {
+            Self::Bazin => &[0, 3, 4],
+            Self::Villar => &[0, 3, 4, 6],
+            Self::Linexp | Self::PowerLaw => &[0, 3],
+        };
+        for &i in positive {
+            params[i] = params[i].abs();
+        }
+        if let Self::Villar = self {
+            params[5] = internal[5].abs().tanh();
+        }
+        params
+    }
+
+    /// Parameters clamped to the `evaluator` bounds
+    fn clamp(self, zp: f64, params: &mut [f64]) {
+        for (x, (lower, upper)) in params.iter_mut().zip(self.bounds(zp)) {
+            *x = x.max(lower.unwrap_or(f64::NEG_INFINITY));
+            *x = x.min(upper.unwrap_or(f64::INFINITY));
+        }
+    }
+
+    /// Transformer of the fits of `light-curve-feature`
+    fn transformer(self, zp: f64) -> Transformer<f64> {
+        match self {
+            Self::Bazin => Transformer::BazinFit(BazinFitTransformer::new(zp)),
+            Self::Villar => Transformer::VillarFit(VillarFitTransformer::new(zp)),
+            Self::Linexp | Self::PowerLaw => unreachable!("in-house fits have no transformer"),
+        }
+    }
+
+    /// Fit evaluator with its output transformed to magnitudes and log-scaled time-scales, only
+    /// the fits of `light-curve-feature` are features
+    pub(super) fn transformed(self, zp: f64, curve_fit: &CurveFit) -> Feature<f64> {
+        let evaluator = self
+            .evaluator(zp, curve_fit)
+            .expect("in-house fits are not features");
+        Transformed::new(evaluator, self.transformer(zp))
+            .unwrap()
+            .into()
+    }
+
+    /// Names of the `transformed_values` of the in-house fits
+    pub(super) fn transformed_names(self) -> Vec<String> {
+        let names: &[&str] = match self {
+            Self::Linexp => &[
+                "mag_peak_amplitude",
+                "baseline_amplitude_ratio",
+                "rise_time",
+            ],
+            Self::PowerLaw => &["mag_amplitude", "baseline_amplitude_ratio", "index"],
+            Self::Bazin | Self::Villar => unreachable!("names of the fits are feature names"),
+        };
+        names
+            .iter()
+            .map(|name| format!("{}{}", self.name_prefix(), name))
+            .chain(std::iter::once(format!(
+                "ln1p_{}reduced_chi2",
+                self.name_prefix()
+            )))
+            .collect()
+    }
+
+    /// Transformed fit values, in-house fits are transformed the same way `BazinFitTransformer`
+    /// does: amplitude in magnitudes, baseline-to-amplitude ratio, shape parameter and
+    /// `ln(1 + reduced χ²)`, the reference time is dropped
+    fn transform(self, zp: f64, params: Vec<f64>, reduced_chi2: f64) -> Vec<f64> {
+        let (amplitude, baseline, shape) = (params[0], params[1], params[3]);
+        let peak = match self {
+            Self::Bazin | Self::Villar => {
+                return self
+                    .transformer(zp)
+                    .transform([params, vec![reduced_chi2]].concat())
+            }
+            // Linexp peaks at A/e above the baseline
+            Self::Linexp => amplitude / std::f64::consts::E,
+            Self::PowerLaw => amplitude,
+        };
+        vec![
+            zp - 2.5 * peak.log10(),
+            baseline / amplitude,
+            shape,
+            reduced_chi2.ln_1p(),
+        ]
+    }
+
+    /// Fit parameters, reduced χ² and the number of Levenberg–Marquardt iterations if there were
+    /// any: fits of `light-curve-feature` are refined by the upper limits of the `constraints`,
+    /// in-house fits use all the constraints
+    fn fit(
+        self,
+        flux_ts: &mut TimeSeries<f64>,
+        constraints: &Constraints,
+        zp: f64,
+        curve_fit: &CurveFit,
+    ) -> Result<(Vec<f64>, f64, Option<usize>), BadRequest<String>> {
+        if let Some(evaluator) = self.evaluator(zp, curve_fit) {
+            let values = evaluator
+                .eval(flux_ts)
+                .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?;
+            return Ok(refine_with_limits(self, constraints, zp, &values));
+        }
+        let n_params = self.parameter_names().len();
+        if constraints.n_detections <= n_params {
+            return Err(BadRequest(Some(format!(
+                "Bad request: {:?} fit requires at least {} detections",
+                self,
+                n_params + 1
+            ))));
+        }
+        let initial = self.initial_params(flux_ts);
+        let (params, chi2, iterations) = censored_fit(self, zp, constraints, &initial);
+        let reduced_chi2 = chi2 / (constraints.n_detections - n_params) as f64;
+        Ok((params, reduced_chi2, Some(iterations)))
+    }
+
+    /// Transformed values of the fit constrained by the upper limits
+    pub(super) fn transformed_values(
+        self,
+        flux_ts: &mut TimeSeries<f64>,
+        limits: &[(f64, f64)],
+        zp: f64,
+        curve_fit: &CurveFit,
+    ) -> Result<Vec<f64>, BadRequest<String>> {
+        let constraints = Constraints::new(flux_ts, limits, zp);
+        let (params, reduced_chi2, _) = self.fit(flux_ts, &constraints, zp, curve_fit)?;
+        Ok(self.transform(zp, params, reduced_chi2))
+    }
+
+    pub(super) fn name_prefix(self) -> &'static str {
+        match self {
+            Self::Bazin => "bazin_fit_",
+            Self::Villar => "villar_fit_",
+            Self::Linexp => "linexp_fit_",
+            Self::PowerLaw => "power_law_fit_",
+        }
+    }
+
+    /// Model flux
+    fn f(self, t: f64, params: &[f64]) -> f64 {
+        match self {
+            Self::Bazin => BazinFit::f(t, params),
+            Self::Villar => VillarFit::f(t, params),
+            Self::Linexp => {
+                let dt = (t - params[2]) / params[3];
+                params[1] + params[0] * dt * f64::exp(-dt)
+            }
+            Self::PowerLaw => {
+                let dt = f64::max(t - params[2], 0.0);
+                params[1] + params[0] * dt.powf(params[3])
+            }
+        }
+    }
+}
+
+/// Flux corresponding to a magnitude, used for fit amplitude bounds
+fn mag_to_flux(mag: f64, zp: f64) -> f64 {
+    f64::powf(10.0, -0.4 * (mag - zp))
+}
+
+/// Time grid to evaluate the model on: either explicit values or a linear grid
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum TimeGrid {
+    Values(Vec<f64>),
+    Linear { start: f64, end: f64, size: usize },
+}
+
+impl TimeGrid {
+    fn values(self, t: ArrayView1<f64>) -> Result<Array1<f64>, BadRequest<String>> {
+        let size = match &self {
+            Self::Values(values) => values.len(),
+            Self::Linear { size, .. } => *size,
+        };
+        if size == 0 {
+            return Ok(Self::default_values(t));
+        }
+        if size > MAX_T_GRID_SIZE {
+            return Err(BadRequest(Some(format!(
+                "Bad request: time grid must have at most {} points",
+                MAX_T_GRID_SIZE
+            ))));
+        }
+        let grid = match self {
+            Self::Values(values) => Array1::from_vec(values),
+            Self::Linear { start, end, size } => Array1::linspace(start, end, size),
+        };
+        if grid.iter().any(|t| !t.is_finite()) {
+            return Err(BadRequest(Some(
+                "Bad request: time grid must be finite".into(),
+            )));
+        }
+        Ok(grid)
+    }
+
+    /// Linear grid over the observed time range
+    fn default_values(t: ArrayView1<f64>) -> Array1<f64> {
+        Array1::linspace(t[0], t[t.len() - 1], DEFAULT_T_GRID_SIZE)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FitData {
+    #[serde(flatten)]
+    data: Data,
+    #[serde(default)]
+    t_grid: Option<TimeGrid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FitParameter {
+    name: String,
+    value: f64,
+    /// Square root of the covariance diagonal
+    error: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelCurve {
+    t: Vec<f64>,
+    flux: Vec<f64>,
+    /// Magnitude, `null` for non-positive flux
+    m: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FitResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    model: FitModel,
+    parameters: Vec<FitParameter>,
+    reduced_chi2: f64,
+    /// Number of non-detections used as one-sided constraints
+    n_upper_limits: usize,
+    /// Number of Levenberg–Marquardt iterations of the in-house Linexp and power-law fits or of
+    /// refining the other fits with the non-detections, `None` if there were no such iterations
+    censored_iterations: Option<usize>,
+    /// Parameter covariance matrix in the order of `parameters`, estimated from the model Jacobian
+    /// at the detections and the violated upper limits
+    covariance: Option<Vec<Vec<f64>>>,
+    /// Requested fit algorithm, its `niterations` are the iteration limits: the solvers don't
+    /// report the number of iterations they actually used
+    curve_fit: CurveFit,
+    /// Solvers don't report their status, so a fit is considered converged if all parameters
+    /// and reduced chi² are finite and the covariance matrix is not singular
+    converged: bool,
+    model_curve: ModelCurve,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
+}
+
+/// Numerical Jacobian of the flux model `f`, flux derivatives for every parameter
+fn jacobian(
+    f: impl Fn(f64, &[f64]) -> f64,
+    t: ArrayView1<f64>,
+    params: &[f64],
+) -> Vec<Array1<f64>> {
+    (0..params.len())
+        .map(|j| {
+            let step = f64::EPSILON.cbrt() * f64::max(params[j].abs(), 1.0);
+            let mut upper = params.to_vec();
+            upper[j] += step;
+            let mut lower = params.to_vec();
+            lower[j] -= step;
+            t.mapv(|t| (f(t, &upper) - f(t, &lower)) / (2.0 * step))
+        })
+        .collect()
+}
+
+/// Detections followed by upper limits as flux constraints of a fit
+struct Constraints {
+    t: Array1<f64>,
+    flux: Array1<f64>,
+    w: Array1<f64>,
+    n_detections: usize,
+}
+
+impl Constraints {
+    /// Upper limits are treated as measurements with the signal-to-noise ratio of the limit
+    fn new(flux_ts: &TimeSeries<f64>, limits: &[(f64, f64)], zp: f64) -> Self {
+        let (limit_t, limit_m): (Vec<f64>, Vec<f64>) = limits.iter().copied().unzip();
+        let limit_flux: Vec<f64> = limit_m.iter().map(|&m| mag_to_flux(m, zp)).collect();
+        let t = flux_ts.t.sample.iter().copied().chain(limit_t).collect();
+        let flux = flux_ts
+            .m
+            .sample
+            .iter()
+            .copied()
+            .chain(limit_flux.iter().copied())
+            .collect();
+        let w = flux_ts
+            .w
+            .sample
+            .iter()
+            .copied()
+            .chain(limit_flux.iter().map(|f| (UPPER_LIMIT_SNR / f).powi(2)))
+            .collect();
+        Self {
+            t,
+            flux,
+            w,
+            n_detections: flux_ts.lenu(),
+        }
+    }
+
+    /// Weighted residuals of the model flux `f`, satisfied upper limits have zero residuals
+    fn residuals(&self, f: impl Fn(f64, &[f64]) -> f64, params: &[f64]) -> Array1<f64> {
+        Array1::from_shape_fn(self.t.len(), |i| {
+            let residual = (f(self.t[i], params) - self.flux[i]) * self.w[i].sqrt();
+            if i < self.n_detections {
+                residual
+            } else {
+                residual.max(0.0)
+            }
+        })
+    }
+
+    /// Weights of the points constraining the parameters, satisfied upper limits don't
+    fn active_weights(&self, residuals: &Array1<f64>) -> Array1<f64> {
+        Array1::from_shape_fn(self.t.len(), |i| {
+            if i < self.n_detections || residuals[i] > 0.0 {
+                self.w[i]
+            } else {
+                0.0
+            }
+        })
+    }
+}
+
+/// Parameter covariance `(JᵀWJ)⁻¹`, where `J` is the model Jacobian and `W` are the weights of the
+/// detections and the violated upper limits
+fn covariance(model: FitModel, constraints: &Constraints, params: &[f64]) -> Option<Array2<f64>> {
+    let f = |t: f64, params: &[f64]| model.f(t, params);
+    let w = constraints.active_weights(&constraints.residuals(f, params));
+    let jacobian = jacobian(f, constraints.t.view(), params);
+    let fisher = Array2::from_shape_fn((params.len(), params.len()), |(a, b)| {
+        (&jacobian[a] * &jacobian[b]).dot(&w)
+    });
+    invert(fisher)
+}
+
+/// Refine fit parameters with Levenberg–Marquardt iterations
+///
+/// Upper limits are one-sided constraints: model fluxes above a limit are penalized as if the limit
+/// was a measurement, fluxes below a limit are not. Iterations run on the unconstrained parameters
+/// as the fits of `light-curve-feature` do, the result is clamped to the `evaluator` bounds.
+/// Returns parameters, their χ² and the number of iterations
+fn censored_fit(
+    model: FitModel,
+    zp: f64,
+    constraints: &Constraints,
+    params: &[f64],
+) -> (Vec<f64>, f64, usize) {
+    let f = |t: f64, internal: &[f64]| model.f(t, &model.to_external(internal));
+
+    let mut params = params.to_vec();
+    model.clamp(zp, &mut params);
+    let mut params = model.to_internal(&params);
+    let mut chi2 = constraints.residuals(f, &params).mapv(|r| r * r).sum();
+    let mut damping = 1e-3;
+    let mut iterations = 0;
+    while iterations < CENSORED_FIT_ITERATIONS {
+        iterations += 1;
+        let r = constraints.residuals(f, &params);
+        let sqrt_w = constraints.active_weights(&r).mapv(f64::sqrt);
+        let jacobian: Vec<Array1<f64>> = jacobian(f, constraints.t.view(), &params)
+            .into_iter()
+            .map(|column| column * &sqrt_w)
+            .collect();
+        let n_params = params.len();
+        let damped = Array2::from_shape_fn((n_params, n_params), |(a, b)| {
+            let product = jacobian[a].dot(&jacobian[b]);
+            if a == b {
+                product * (1.0 + damping)
+            } else {
+                product
+            }
+        });
+        let gradient = Array1::from_shape_fn(n_params, |a| jacobian[a].dot(&r));
+        let step = match invert(damped) {
+            Some(inverse) => inverse.dot(&gradient),
+            None => break,
+        };
+        let candidate: Vec<f64> = params.iter().zip(step.iter()).map(|(p, s)| p - s).collect();
+        let candidate_chi2 = constraints.residuals(f, &candidate).mapv(|r| r * r).sum();
+        if candidate_chi2 < chi2 {
+            let converged = chi2 - candidate_chi2 <= 1e-10 * chi2;
+            params = candidate;
+            chi2 = candidate_chi2;
+            damping *= 0.1;
+            if converged {
+                break;
+            }
+        } else {
+            damping *= 10.0;
+            if damping > 1e10 {
+                break;
+            }
+        }
+    }
+    let mut params = model.to_external(&params);
+    model.clamp(zp, &mut params);
+    let chi2 = constraints
+        .residuals(|t, params| model.f(t, params), &params)
+        .mapv(|r| r * r)
+        .sum();
+    (params, chi2, iterations)
+}
+
+/// Fit parameters, reduced χ² and the number of refinement iterations of the evaluator `values`
+/// refined with the upper limits of the `constraints`, the values are returned unchanged if there
+/// are no upper limits
+fn refine_with_limits(
+    model: FitModel,
+    constraints: &Constraints,
+    zp: f64,
+    values: &[f64],
+) -> (Vec<f64>, f64, Option<usize>) {
+    let (params, reduced_chi2) = values.split_at(values.len() - 1);
+    if constraints.t.len() == constraints.n_detections {
+        return (params.to_vec(), reduced_chi2[0], None);
+    }
+    let (params, chi2, iterations) = censored_fit(model, zp, constraints, params);
+    let n_detections = constraints.n_detections as f64;
+    let reduced_chi2 = chi2 / (n_detections - params.len() as f64);
+    (params, reduced_chi2, Some(iterations))
+}
+
+/// Parametric fit of the flux light curve with diagnostics and the model light curve
+///
+/// Non-detections are used as one-sided constraints refining the fit of the detections
+#[post(
+    "/fit/<model>?<meta>",
+    format = "application/json",
+    data = "<fit_data>"
+)]
+pub fn fit(
+    model: FitModel,
+    fit_data: JsonInput<FitData>,
+    meta: Option<bool>,
+    log: &RequestLog,
+) -> Result<Json<FitResponse>, BadRequest<String>> {
+    let started = Instant::now();
+    let FitData { data, t_grid } = fit_data.0;
+    let Data {
+        id,
+        light_curve,
+        zero_point,
+        curve_fit,
+        preprocessing,
+        time_correction,
+    } = data;
+    let zp = zero_point.value()?;
+    curve_fit.validate()?;
+    let n_obs_input = light_curve.len();
+    log.object(&id, n_obs_input);
+    let limits = upper_limits(&light_curve);
+
+    let (mag_ts, removed_indices) = preprocessed_time_series(light_curve, &preprocessing)?;
+    let mut flux_ts = flux_ts_from_mag_ts(&mag_ts, zp);
+
+    let constraints = Constraints::new(&flux_ts, &limits, zp);
+    let (params, reduced_chi2, censored_iterations) =
+        model.fit(&mut flux_ts, &constraints, zp, &curve_fit)?;
+
+    let covariance = covariance(model, &constraints, &params);
+    let parameters = model
+        .parameter_names()
+        .iter()
+        .zip(&params)
+        .enumerate()
+        .map(|(i, (&name, &value))| FitParameter {
+            name: name.to_owned(),
+            value,
+            error: covariance.as_ref().map(|cov| cov[[i, i]].sqrt()),
+        })
+        .collect();
+    let converged =
+        covariance.is_some() && reduced_chi2.is_finite() && params.iter().all(|x| x.is_finite());
+
+    let t_grid = match t_grid {
+        Some(t_grid) => t_grid.values(flux_ts.t.sample.view())?,
+        None => TimeGrid::default_values(flux_ts.t.sample.view()),
+    };
+    let flux = t_grid.mapv(|t| model.f(t, &params));
+    let model_curve = ModelCurve {
+        m: flux
+            .iter()
+            .map(|&f| (f > 0.0).then(|| zp - 2.5 * f.log10()))
+            .collect(),
+        t: t_grid.to_vec(),
+        flux: flux.to_vec(),
+    };
+
+    let meta = meta.unwrap_or(false).then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            match model.evaluator(zp, &curve_fit) {
+                Some(evaluator) => extractor_hash(&evaluator),
+                None => extractor_hash(&(model, model.bounds(zp))),
+            },
+            n_obs_input,
+            mag_ts.t.sample.as_slice().unwrap(),
+            Some(zp),
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+    });
+    Ok(Json(FitResponse {
+        id,
+        model,
+        parameters,
+        reduced_chi2,
+        n_upper_limits: limits.len(),
+        censored_iterations,
+        covariance: covariance.map(|cov| cov.outer_iter().map(|row| row.to_vec()).collect()),
+        curve_fit,
+        converged,
+        model_curve,
+        meta,
+    }))
+}
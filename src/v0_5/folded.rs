@@ -0,0 +1,138 @@
+use light_curve_feature_0_5::ndarray::Array1;
+use light_curve_feature_0_5::*;
+use rocket::response::status::BadRequest;
+use rocket::serde::{json::Json, Deserialize};
+use std::time::Instant;
+use unzip3::Unzip3;
+
+use super::periodogram::PeriodogramSettings;
+use super::{
+    extractor_hash, preprocessed_time_series, Data, FeatureValues, Response,
+    LIGHT_CURVE_FEATURE_VERSION,
+};
+use crate::access_log::RequestLog;
+use crate::json_input::JsonInput;
+use crate::meta::Meta;
+
+thread_local! {
+    static FOLDED_FE: FeatureExtractor<f64, Feature<f64>> = FeatureExtractor::from_features(vec![
+        Amplitude::default().into(),
+        Cusum::new().into(),
+        Eta::default().into(),
+        InterPercentileRange::new(0.25).into(),
+        StandardDeviation::default().into(),
+    ]);
+}
+
+/// Period of the highest periodogram peak
+pub(super) fn best_period(
+    ts: &mut TimeSeries<f64>,
+    settings: &PeriodogramSettings,
+) -> Result<f64, BadRequest<String>> {
+    if ts.lenu() > 1 {
+        settings.freq_grid(ts.t.sample.as_slice().unwrap())?;
+    }
+    let values = settings
+        .periodogram()
+        .eval(ts)
+        .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?;
+    match values[0] {
+        period if period.is_finite() && period > 0.0 => Ok(period),
+        _ => Err(BadRequest(Some(
+            "Bad request: light curve has no valid periodogram peak".into(),
+        ))),
+    }
+}
+
+/// Fold light curve with a period, time of the folded series is phase in `[0, 1)`
+pub(super) fn fold(ts: &TimeSeries<f64>, period: f64) -> TimeSeries<'static, f64> {
+    let t0 = ts.t.sample[0];
+    let mut points: Vec<_> =
+        ts.t.sample
+            .iter()
+            .zip(ts.m.sample.iter())
+            .zip(ts.w.sample.iter())
+            .map(|((&t, &m), &w)| (((t - t0) / period).rem_euclid(1.0), m, w))
+            .collect();
+    points.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    let (phase, m, w): (Vec<_>, Vec<_>, Vec<_>) = points.into_iter().unzip3();
+    TimeSeries::new(
+        Array1::from_vec(phase),
+        Array1::from_vec(m),
+        Array1::from_vec(w),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FoldedData {
+    #[serde(flatten)]
+    data: Data,
+    #[serde(default)]
+    periodogram: PeriodogramSettings,
+    /// Features to evaluate on the folded light curve, default is a set of variability features
+    #[serde(default)]
+    extractor: Option<Feature<f64>>,
+}
+
+/// Features of the light curve folded with the period of the highest periodogram peak
+///
+/// Feature names are prefixed with `folded_`, the period itself is `folded_period`
+#[post("/folded?<meta>", format = "application/json", data = "<folded_data>")]
+pub fn folded(
+    folded_data: JsonInput<FoldedData>,
+    meta: Option<bool>,
+    log: &RequestLog,
+) -> Result<Json<Response>, BadRequest<String>> {
+    let started = Instant::now();
+    let FoldedData {
+        data,
+        periodogram,
+        extractor,
+    } = folded_data.0;
+    periodogram.validate()?;
+    let n_obs_input = data.light_curve.len();
+    log.object(&data.id, n_obs_input);
+
+    let (mut ts, removed_indices) =
+        preprocessed_time_series(data.light_curve, &data.preprocessing)?;
+    let (id, time_correction) = (data.id, data.time_correction);
+    let period = best_period(&mut ts, &periodogram)?;
+    let mut folded_ts = fold(&ts, period);
+
+    let (names, values, hash): (Vec<String>, _, _) = match &extractor {
+        Some(fe) => (
+            fe.get_names().iter().map(|s| s.to_string()).collect(),
+            fe.eval(&mut folded_ts),
+            extractor_hash(&(&periodogram, fe)),
+        ),
+        None => FOLDED_FE.with(|fe| {
+            (
+                fe.get_names().iter().map(|s| s.to_string()).collect(),
+                fe.eval(&mut folded_ts),
+                extractor_hash(&(&periodogram, fe)),
+            )
+        }),
+    };
+    let values = values.map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?;
+
+    let features: FeatureValues = names
+        .into_iter()
+        .map(|name| format!("folded_{}", name))
+        .zip(values)
+        .chain(std::iter::once(("folded_period".to_owned(), period)))
+        .collect();
+
+    let meta = meta.unwrap_or(false).then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            hash,
+            n_obs_input,
+            ts.t.sample.as_slice().unwrap(),
+            None,
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+    });
+    Ok(Json(Response { id, features, meta }))
+}
@@ -0,0 +1,113 @@
+use light_curve_feature_0_5::ndarray::{Array1, Array2, Axis};
+use light_curve_feature_0_5::TimeSeries;
+use rocket::response::status::BadRequest;
+use rocket::serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use super::folded::best_period;
+use super::linalg::invert;
+use super::periodogram::PeriodogramSettings;
+
+/// Maximum number of harmonics
+const MAX_NHARMONICS: usize = 10;
+
+pub const HARMONIC_FIT_NAMES: [&str; 7] = [
+    "harmonic_period",
+    "harmonic_amplitude_1",
+    "harmonic_r21",
+    "harmonic_r31",
+    "harmonic_phi21",
+    "harmonic_phi31",
+    "harmonic_reduced_chi2",
+];
+
+/// Multi-harmonic Fourier series fit at the best periodogram period
+///
+/// Magnitudes are fitted by `A_0 + Σ A_k sin(kωt + φ_k)`, amplitude ratios are `R_k1 = A_k / A_1`
+/// and phase differences are `φ_k1 = φ_k - k φ_1` in `[0, 2π)`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HarmonicFitSettings {
+    pub nharmonics: usize,
+    pub periodogram: PeriodogramSettings,
+}
+
+impl Default for HarmonicFitSettings {
+    fn default() -> Self {
+        Self {
+            nharmonics: 4,
+            periodogram: PeriodogramSettings::default(),
+        }
+    }
+}
+
+impl HarmonicFitSettings {
+    pub fn validate(&self) -> Result<(), BadRequest<String>> {
+        if !(3..=MAX_NHARMONICS).contains(&self.nharmonics) {
+            return Err(BadRequest(Some(format!(
+                "Bad request: number of harmonics must be from 3 to {}",
+                MAX_NHARMONICS
+            ))));
+        }
+        self.periodogram.validate()
+    }
+
+    /// Feature values in the order of `HARMONIC_FIT_NAMES`
+    pub fn eval(&self, ts: &mut TimeSeries<f64>) -> Result<Vec<f64>, BadRequest<String>> {
+        let n_params = 2 * self.nharmonics + 1;
+        if ts.lenu() <= n_params {
+            return Err(BadRequest(Some(format!(
+                "Bad request: harmonic fit with {} harmonics needs more than {} observations",
+                self.nharmonics, n_params
+            ))));
+        }
+
+        let period = best_period(ts, &self.periodogram)?;
+        let omega = 2.0 * PI / period;
+        let t0 = ts.t.sample[0];
+
+        // Design matrix columns are 1, sin(kωt), cos(kωt)
+        let design = Array2::from_shape_fn((ts.lenu(), n_params), |(i, j)| {
+            let phase = (((j + 1) / 2) as f64) * omega * (ts.t.sample[i] - t0);
+            match j {
+                0 => 1.0,
+                j if j % 2 == 1 => phase.sin(),
+                _ => phase.cos(),
+            }
+        });
+        let weighted_design = &design * &ts.w.sample.view().insert_axis(Axis(1));
+        let normal = weighted_design.t().dot(&design);
+        let rhs = weighted_design.t().dot(&ts.m.sample);
+        let params: Array1<f64> = invert(normal)
+            .ok_or_else(|| {
+                BadRequest(Some(
+                    "Bad request: harmonic fit is degenerate for this light curve".into(),
+                ))
+            })?
+            .dot(&rhs);
+
+        let residuals = &ts.m.sample - &design.dot(&params);
+        let chi2 = (&residuals * &residuals * &ts.w.sample).sum();
+        let reduced_chi2 = chi2 / (ts.lenu() - n_params) as f64;
+
+        let (amplitudes, phases): (Vec<f64>, Vec<f64>) = (1..=self.nharmonics)
+            .map(|k| {
+                let sin = params[2 * k - 1];
+                let cos = params[2 * k];
+                (f64::hypot(sin, cos), f64::atan2(cos, sin))
+            })
+            .unzip();
+        let ratio = |k: usize| amplitudes[k - 1] / amplitudes[0];
+        let phase_diff = |k: usize| (phases[k - 1] - k as f64 * phases[0]).rem_euclid(2.0 * PI);
+
+        Ok(vec![
+            period,
+            amplitudes[0],
+            ratio(2),
+            ratio(3),
+            phase_diff(2),
+            phase_diff(3),
+            reduced_chi2,
+        ])
+    }
+}
@@ -0,0 +1,56 @@
+use super::Observation;
+
+/// Signal-to-noise ratio of a source at the limiting magnitude
+pub const UPPER_LIMIT_SNR: f64 = 5.0;
+
+pub const UPPER_LIMIT_NAMES: [&str; 3] = [
+    "detection_fraction",
+    "time_since_last_non_detection",
+    "rise_from_limit_rate",
+];
+
+/// Times and limiting magnitudes of the non-detections, sorted by time
+pub fn upper_limits(light_curve: &[Observation]) -> Vec<(f64, f64)> {
+    let mut limits: Vec<(f64, f64)> = light_curve
+        .iter()
+        .filter(|obs| obs.upper_limit)
+        .map(|obs| (obs.t, obs.m))
+        .collect();
+    limits.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    limits
+}
+
+/// Feature values in the order of `UPPER_LIMIT_NAMES`
+///
+/// The last non-detection preceding the first detection gives the time since the last
+/// non-detection and the rise rate in magnitudes per time unit, both are NaN if there is no such
+/// non-detection
+pub fn upper_limit_features(light_curve: &[Observation]) -> Vec<f64> {
+    let n_detections = light_curve.iter().filter(|obs| !obs.upper_limit).count();
+    let detection_fraction = n_detections as f64 / light_curve.len() as f64;
+
+    let first_detection = light_curve
+        .iter()
+        .filter(|obs| !obs.upper_limit)
+        .min_by(|a, b| a.t.total_cmp(&b.t));
+    let last_limit = first_detection.and_then(|first| {
+        light_curve
+            .iter()
+            .filter(|obs| obs.upper_limit && obs.t < first.t)
+            .max_by(|a, b| a.t.total_cmp(&b.t))
+            .map(|limit| (first, limit))
+    });
+    let (time_since_last_non_detection, rise_from_limit_rate) = match last_limit {
+        Some((first, limit)) => {
+            let dt = first.t - limit.t;
+            (dt, (limit.m - first.m) / dt)
+        }
+        None => (f64::NAN, f64::NAN),
+    };
+
+    vec![
+        detection_fraction,
+        time_since_last_non_detection,
+        rise_from_limit_rate,
+    ]
+}
@@ -0,0 +1,32 @@
+use light_curve_feature_0_5::ndarray::Array2;
+
+/// Gauss–Jordan matrix inversion with partial pivoting, `None` for singular matrices
+pub fn invert(mut a: Array2<f64>) -> Option<Array2<f64>> {
+    let n = a.nrows();
+    let scale = a.iter().fold(0.0_f64, |acc, x| acc.max(x.abs()));
+    if !scale.is_finite() || scale == 0.0 {
+        return None;
+    }
+    let mut inv = Array2::eye(n);
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[[i, col]].abs().total_cmp(&a[[j, col]].abs()))?;
+        if a[[pivot, col]].abs() <= 1e-14 * scale {
+            return None;
+        }
+        for k in 0..n {
+            a.swap([col, k], [pivot, k]);
+            inv.swap([col, k], [pivot, k]);
+        }
+        let p = a[[col, col]];
+        a.row_mut(col).mapv_inplace(|x| x / p);
+        inv.row_mut(col).mapv_inplace(|x| x / p);
+        let a_pivot_row = a.row(col).to_owned();
+        let inv_pivot_row = inv.row(col).to_owned();
+        for row in (0..n).filter(|&row| row != col) {
+            let factor = a[[row, col]];
+            a.row_mut(row).scaled_add(-factor, &a_pivot_row);
+            inv.row_mut(row).scaled_add(-factor, &inv_pivot_row);
+        }
+    }
+    Some(inv)
+}
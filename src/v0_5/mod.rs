@@ -0,0 +1,1138 @@
+use light_curve_feature_0_5::ndarray::{Array1, Zip};
+use light_curve_feature_0_5::transformers::{
+    arcsinh::ArcsinhTransformer, composed::ComposedTransformer, identity::IdentityTransformer,
+    lg::LgTransformer, ln1p::Ln1pTransformer,
+};
+use light_curve_feature_0_5::*;
+use rocket::response::status::BadRequest;
+use rocket::serde::{
+    json::{serde_json, Json, Value},
+    Deserialize, Serialize,
+};
+use rocket::State;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Instant;
+
+use crate::access_log::{ObjectId, RequestLog};
+use crate::cache::{self, CacheStatus, ResultCache};
+use crate::hash::hex_digest;
+use crate::json_input::JsonInput;
+use crate::meta::Meta;
+use crate::registry::ApiVersion;
+
+pub mod dmdt;
+pub mod ephemeris;
+use ephemeris::{TimeCorrection, TimeCorrectionRange};
+pub mod extinction;
+use extinction::Extinction;
+pub mod fap;
+pub mod fit;
+use fit::{FitModel, EXTRACTOR_MODELS, IN_HOUSE_MODELS};
+pub mod folded;
+pub mod harmonics;
+use harmonics::{HarmonicFitSettings, HARMONIC_FIT_NAMES};
+pub mod limits;
+use limits::{upper_limit_features, upper_limits, UPPER_LIMIT_NAMES};
+mod linalg;
+pub mod multiband;
+pub mod periodogram;
+use periodogram::PeriodogramSettings;
+pub mod preprocessing;
+use preprocessing::{reject_outliers, OutlierRejection};
+pub mod times;
+use times::{TimeFormat, TimeSettings, TimeValue};
+pub mod windows;
+
+pub const LIGHT_CURVE_FEATURE_VERSION: &str = "0.5.5";
+
+pub const MAG_ZP_F64: f64 = 8.9 + 6.0 * 2.5; // μJy
+
+/// Minimum number of detections of a light curve
+const MIN_N_OBS: usize = 5;
+
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawData")]
+pub struct Data {
+    id: Option<ObjectId>,
+    light_curve: Vec<Observation>,
+    zero_point: ZeroPoint,
+    curve_fit: CurveFit,
+    preprocessing: Vec<OutlierRejection>,
+    time_correction: Option<TimeCorrectionRange>,
+}
+
+/// Request data with times in the requested unit and format
+#[derive(Deserialize)]
+struct RawData {
+    /// Optional object identifier echoed in the response
+    #[serde(default)]
+    id: Option<ObjectId>,
+    light_curve: Vec<RawObservation>,
+    #[serde(default)]
+    time: TimeSettings,
+    /// Optional light-travel time correction of absolute times
+    #[serde(default)]
+    time_correction: Option<TimeCorrection>,
+    /// Optional Galactic extinction correction of magnitudes
+    #[serde(default)]
+    extinction: Option<Extinction>,
+    #[serde(default)]
+    zero_point: ZeroPoint,
+    #[serde(default)]
+    curve_fit: CurveFit,
+    /// Outlier rejection steps applied in order before the evaluation
+    #[serde(default)]
+    preprocessing: Vec<OutlierRejection>,
+}
+
+impl TryFrom<RawData> for Data {
+    type Error = String;
+
+    fn try_from(raw: RawData) -> Result<Self, Self::Error> {
+        let (mut light_curve, time_correction) =
+            light_curve_in_days(raw.light_curve, raw.time, raw.time_correction)?;
+        if let Some(extinction) = &raw.extinction {
+            extinction.apply(&mut light_curve)?;
+        }
+        Ok(Self {
+            id: raw.id,
+            light_curve,
+            zero_point: raw.zero_point,
+            curve_fit: raw.curve_fit,
+            preprocessing: raw.preprocessing,
+            time_correction,
+        })
+    }
+}
+
+/// Magnitude zero point used to convert magnitudes to fluxes
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum ZeroPoint {
+    /// Arbitrary zero point, e.g. a survey-specific one
+    Value(f64),
+    /// AB-system flux unit
+    Unit(FluxUnit),
+}
+
+impl ZeroPoint {
+    fn value(self) -> Result<f64, BadRequest<String>> {
+        match self {
+            Self::Value(zp) if zp.is_finite() => Ok(zp),
+            Self::Value(_) => Err(BadRequest(Some(
+                "Bad request: zero point must be finite".into(),
+            ))),
+            Self::Unit(unit) => Ok(unit.ab_zero_point()),
+        }
+    }
+}
+
+impl Default for ZeroPoint {
+    fn default() -> Self {
+        Self::Value(MAG_ZP_F64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum FluxUnit {
+    Jy,
+    #[serde(rename = "mJy")]
+    MilliJy,
+    #[serde(rename = "uJy", alias = "μJy")]
+    MicroJy,
+    #[serde(rename = "nJy")]
+    NanoJy,
+}
+
+impl FluxUnit {
+    /// Zero point of AB magnitudes for fluxes in this unit
+    fn ab_zero_point(self) -> f64 {
+        let jy_zp = 8.9;
+        match self {
+            Self::Jy => jy_zp,
+            Self::MilliJy => jy_zp + 3.0 * 2.5,
+            Self::MicroJy => jy_zp + 6.0 * 2.5,
+            Self::NanoJy => jy_zp + 9.0 * 2.5,
+        }
+    }
+}
+
+/// Curve-fit algorithm of the parametric fits, e.g. `{"algorithm": "ceres", "niterations": 20}`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum CurveFit {
+    Ceres {
+        #[serde(default = "CurveFit::default_ceres_niterations")]
+        niterations: u16,
+        #[serde(default)]
+        loss_factor: Option<f64>,
+    },
+    Lmsder {
+        #[serde(default = "CurveFit::default_lmsder_niterations")]
+        niterations: u16,
+    },
+    Mcmc {
+        #[serde(default = "CurveFit::default_mcmc_niterations")]
+        niterations: u32,
+        /// Optional algorithm to refine the MCMC solution
+        #[serde(default)]
+        fine_tuning: Option<Box<CurveFit>>,
+    },
+    /// MCMC initialization refined by Ceres
+    Robust {
+        #[serde(default = "CurveFit::default_mcmc_niterations")]
+        mcmc_niterations: u32,
+        #[serde(default = "CurveFit::default_ceres_niterations")]
+        ceres_niterations: u16,
+    },
+}
+
+impl CurveFit {
+    fn default_ceres_niterations() -> u16 {
+        20
+    }
+
+    fn default_lmsder_niterations() -> u16 {
+        10
+    }
+
+    fn default_mcmc_niterations() -> u32 {
+        128
+    }
+
+    fn validate(&self) -> Result<(), BadRequest<String>> {
+        let valid = match self {
+            Self::Ceres {
+                niterations,
+                loss_factor,
+            } => *niterations > 0 && loss_factor.map_or(true, |x| x.is_finite() && x > 0.0),
+            Self::Lmsder { niterations } => *niterations > 0,
+            Self::Mcmc {
+                niterations,
+                fine_tuning,
+            } => {
+                if let Some(fine_tuning) = fine_tuning {
+                    fine_tuning.validate()?;
+                }
+                *niterations > 0
+            }
+            Self::Robust {
+                mcmc_niterations,
+                ceres_niterations,
+            } => *mcmc_niterations > 0 && *ceres_niterations > 0,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(BadRequest(Some(
+                "Bad request: curve fit iteration number and loss factor must be positive".into(),
+            )))
+        }
+    }
+
+    fn algorithm(&self) -> CurveFitAlgorithm {
+        match self {
+            Self::Ceres {
+                niterations,
+                loss_factor,
+            } => CeresCurveFit::new(*niterations, *loss_factor).into(),
+            Self::Lmsder { niterations } => LmsderCurveFit::new(*niterations).into(),
+            Self::Mcmc {
+                niterations,
+                fine_tuning,
+            } => McmcCurveFit::new(
+                *niterations,
+                fine_tuning
+                    .as_ref()
+                    .map(|fine_tuning| fine_tuning.algorithm()),
+            )
+            .into(),
+            Self::Robust {
+                mcmc_niterations,
+                ceres_niterations,
+            } => McmcCurveFit::new(
+                *mcmc_niterations,
+                Some(CeresCurveFit::new(*ceres_niterations, None).into()),
+            )
+            .into(),
+        }
+    }
+}
+
+impl Default for CurveFit {
+    fn default() -> Self {
+        Self::Ceres {
+            niterations: Self::default_ceres_niterations(),
+            loss_factor: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawObservation {
+    t: TimeValue,
+    m: f64,
+    /// Required for detections only
+    #[serde(default)]
+    err: Option<f64>,
+    /// Passband name, used by the multi-band periodogram only
+    band: Option<String>,
+    /// Non-detection, `m` is the limiting magnitude and `err` is ignored
+    #[serde(default)]
+    upper_limit: bool,
+}
+
+/// Observation with time in days
+#[derive(Debug)]
+struct Observation {
+    t: f64,
+    m: f64,
+    /// NaN for upper limits without error
+    err: f64,
+    band: Option<String>,
+    upper_limit: bool,
+}
+
+/// Observations with times converted to days and optionally corrected for the light-travel time,
+/// the range of the applied corrections is returned
+fn light_curve_in_days(
+    light_curve: Vec<RawObservation>,
+    time: TimeSettings,
+    time_correction: Option<TimeCorrection>,
+) -> Result<(Vec<Observation>, Option<TimeCorrectionRange>), String> {
+    let mut light_curve = light_curve
+        .into_iter()
+        .map(|obs| {
+            let err = match obs.err {
+                Some(err) => err,
+                None if obs.upper_limit => f64::NAN,
+                None => return Err("observation error \"err\" is required for detections".into()),
+            };
+            Ok(Observation {
+                t: time.days(&obs.t)?,
+                m: obs.m,
+                err,
+                band: obs.band,
+                upper_limit: obs.upper_limit,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let time_correction = match time_correction {
+        Some(_) if time.format == TimeFormat::Number => return Err(
+            "time correction requires absolute times, specify time format other than \"number\""
+                .into(),
+        ),
+        Some(time_correction) => {
+            let mut t: Vec<f64> = light_curve.iter().map(|obs| obs.t).collect();
+            let range = time_correction.apply(&mut t)?;
+            for (obs, t) in light_curve.iter_mut().zip(t) {
+                obs.t = t;
+            }
+            Some(range)
+        }
+        None => None,
+    };
+
+    Ok((light_curve, time_correction))
+}
+
+thread_local! {
+    static MAG_FE: FeatureExtractor<f64, Feature<f64>> = {
+        let bins: Feature<f64> = {
+            let eta_e: Feature<f64> = {
+                let feature: Feature<f64> = EtaE::default().into();
+                let transformer: Transformer<f64> = Ln1pTransformer {}.into();
+                Transformed::new(feature, transformer).unwrap().into()
+            };
+            let linear_fit: Feature<f64> = {
+                let feature: Feature<f64> = LinearFit::default().into();
+                let transformer: Transformer<f64> = ComposedTransformer::new(vec![
+                    (ArcsinhTransformer {}.into(), 1), // slope
+                    (LgTransformer {}.into(), 1),      // slope sigma
+                    (Ln1pTransformer {}.into(), 1),    // reduced chi2
+                ])
+                .unwrap()
+                .into();
+                Transformed::new(feature, transformer).unwrap().into()
+            };
+            let linear_trend: Feature<f64> = {
+                let feature: Feature<f64> = LinearTrend::default().into();
+                let transformer: Transformer<f64> = ComposedTransformer::new(vec![
+                    (ArcsinhTransformer {}.into(), 1), // trend
+                    (LgTransformer {}.into(), 1),      // trend sigma
+                    (LgTransformer {}.into(), 1),      // noise
+                ])
+                .unwrap()
+                .into();
+                Transformed::new(feature, transformer).unwrap().into()
+            };
+            let maximum_slope: Feature<f64> = {
+                let feature: Feature<f64> = MaximumSlope::default().into();
+                let transformer: Transformer<f64> = LgTransformer {}.into();
+                Transformed::new(feature, transformer).unwrap().into()
+            };
+
+            let mut bins = Bins::new(1.0, 0.0);
+            bins.add_feature(Cusum::new().into());
+            bins.add_feature(eta_e);
+            bins.add_feature(linear_fit);
+            bins.add_feature(linear_trend);
+            bins.add_feature(maximum_slope);
+
+            bins.into()
+        };
+
+        let inter_percentile_range_02: Feature<f64> = {
+            let feature = InterPercentileRange::new(0.02).into();
+            let transformer: Transformer<f64> = LgTransformer {}.into();
+            Transformed::new(feature, transformer).unwrap().into()
+        };
+        let inter_percentile_range_10: Feature<f64> = {
+            let feature = InterPercentileRange::new(0.1).into();
+            let transformer: Transformer<f64> = LgTransformer {}.into();
+            Transformed::new(feature, transformer).unwrap().into()
+        };
+        let inter_percentile_range_25: Feature<f64> = {
+            let feature = InterPercentileRange::new(0.25).into();
+            let transformer: Transformer<f64> = LgTransformer {}.into();
+            Transformed::new(feature, transformer).unwrap().into()
+        };
+
+        let periodogram: Feature<f64> = {
+            let mut periodogram = Periodogram::new(5);
+            periodogram.set_nyquist(NyquistFreq::median());
+            periodogram.set_freq_resolution(10.0);
+            periodogram.set_max_freq_factor(2.0);
+            periodogram.into()
+        };
+
+        let otsu_split: Feature<f64> = {
+            let feature = OtsuSplit::new().into();
+            let transformer: Transformer<f64> = ComposedTransformer::new(vec![
+                (LgTransformer {}.into(), 1),       // mean diff
+                (IdentityTransformer {}.into(), 1), // std lower
+                (IdentityTransformer {}.into(), 1), // std upper
+                (IdentityTransformer {}.into(), 1), // lower to all ratio
+            ])
+            .unwrap()
+            .into();
+            Transformed::new(feature, transformer).unwrap().into()
+        };
+
+        let reduced_chi2: Feature<f64> = {
+            let feature = ReducedChi2::new().into();
+            let transformer: Transformer<f64> = Ln1pTransformer {}.into();
+            Transformed::new(feature, transformer).unwrap().into()
+        };
+
+        let skew: Feature<f64> = {
+            let feature = Skew::new().into();
+            let transformer: Transformer<f64> = ArcsinhTransformer {}.into();
+            Transformed::new(feature, transformer).unwrap().into()
+        };
+
+        FeatureExtractor::from_features(vec![
+            BeyondNStd::new(1.0).into(), // default
+            BeyondNStd::new(2.0).into(),
+            bins,
+            inter_percentile_range_02,
+            inter_percentile_range_25,
+            inter_percentile_range_10,
+            Kurtosis::new().into(),
+            otsu_split,
+            periodogram,
+            reduced_chi2,
+            skew,
+            StetsonK::new().into(),
+            WeightedMean::new().into(),
+        ])
+
+    };
+
+    static FLUX_FE: FeatureExtractor<f64, Feature<f64>> =
+        flux_feature_extractor(MAG_ZP_F64, &CurveFit::default());
+
+    static FEATURE_NAMES: Vec<String> = {
+        let magn_fe_names: Vec<String> = MAG_FE.with(|fe| fe.get_names().iter().map(|s| s.to_string()).collect());
+        let flux_fe_names: Vec<String> = FLUX_FE.with(|fe| fe.get_names().iter().map(|s| s.to_string()).collect());
+        magn_fe_names
+            .iter()
+            .map(|name| (name, "magn"))
+            .chain(flux_fe_names.iter().map(|name| (name, "flux")))
+            .map(|(name, brightness_type)| {
+                format!("{}_{}", name, brightness_type)
+            })
+            .collect()
+    };
+
+    static EXTRACTOR_HASH: String = MAG_FE.with(|mag_fe| {
+        FLUX_FE.with(|flux_fe| extractor_hash(&(mag_fe, flux_fe)))
+    });
+
+    static TRANSIENT_FE: FeatureExtractor<f64, Feature<f64>> =
+        transient_feature_extractor(MAG_ZP_F64, &CurveFit::default());
+
+    static TRANSIENT_FEATURE_NAMES: Vec<String> = TRANSIENT_FE.with(|fe| {
+        fe.get_names()
+            .into_iter()
+            .map(String::from)
+            .chain(IN_HOUSE_MODELS.iter().flat_map(|model| model.transformed_names()))
+            .map(|name| format!("{}_flux", name))
+            .collect()
+    });
+
+    static TRANSIENT_EXTRACTOR_HASH: String =
+        TRANSIENT_FE.with(|fe| extractor_hash(&(fe, IN_HOUSE_MODELS)));
+}
+
+/// Flux-branch extractor for fluxes defined by a magnitude zero point
+fn flux_feature_extractor(zp: f64, curve_fit: &CurveFit) -> FeatureExtractor<f64, Feature<f64>> {
+    let anderson_darling_normal: Feature<f64> = {
+        let feature = AndersonDarlingNormal::default().into();
+        let transformer: Transformer<f64> = Ln1pTransformer {}.into();
+        Transformed::new(feature, transformer).unwrap().into()
+    };
+
+    FeatureExtractor::from_features(vec![
+        anderson_darling_normal,
+        FitModel::Bazin.transformed(zp, curve_fit),
+        ExcessVariance::new().into(),
+    ])
+}
+
+/// Parametric fits of `light-curve-feature` for transient classification, fluxes are defined by a
+/// magnitude zero point. The `IN_HOUSE_MODELS` fits follow them
+fn transient_feature_extractor(
+    zp: f64,
+    curve_fit: &CurveFit,
+) -> FeatureExtractor<f64, Feature<f64>> {
+    FeatureExtractor::from_features(vec![
+        FitModel::Bazin.transformed(zp, curve_fit),
+        FitModel::Villar.transformed(zp, curve_fit),
+    ])
+}
+
+type FeatureValues = HashMap<String, f64>;
+
+#[derive(Debug, Serialize)]
+pub struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    #[serde(flatten)]
+    features: FeatureValues,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
+}
+
+/// Response with `X-Cache` header
+#[derive(Responder)]
+pub struct CachedResponse {
+    inner: Json<Response>,
+    cache: CacheStatus,
+}
+
+/// Result cache unless it is bypassed by `?cache=false`
+///
+/// All curve fits are deterministic: `light-curve-feature` v0.5.5 MCMC uses a fixed seed
+fn result_cache(state: &State<ResultCache>, cache: bool) -> Option<&ResultCache> {
+    Some(state.inner()).filter(|_| cache)
+}
+
+/// Cache key of the preprocessed time series
+fn cache_key(route: &str, extractor: &str, options: &str, ts: &TimeSeries<f64>) -> Vec<u8> {
+    cache::key(
+        route,
+        extractor,
+        options,
+        ts.t.sample.as_slice().unwrap(),
+        ts.m.sample.as_slice().unwrap(),
+        ts.w.sample.as_slice().unwrap(),
+    )
+}
+
+fn extractor_hash<F: Serialize>(extractor: &F) -> String {
+    let definition = serde_json::to_vec(extractor).expect("extractor must be serializable");
+    hex_digest(&definition)
+}
+
+fn data_to_time_series(
+    data: Vec<Observation>,
+) -> Result<TimeSeries<'static, f64>, BadRequest<String>> {
+    let n_obs = data.len();
+    if n_obs < MIN_N_OBS {
+        return Err(BadRequest(Some(
+            "Bad request: Light curve must have at least five observations".into(),
+        )));
+    }
+    Ok(observations_to_time_series(data))
+}
+
+/// Time series of observations sorted by time, without length check
+fn observations_to_time_series(mut data: Vec<Observation>) -> TimeSeries<'static, f64> {
+    let n_obs = data.len();
+    data.sort_unstable_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+
+    let (time, mag, mag_weight) = {
+        let mut t = Array1::zeros(n_obs);
+        let mut mag = Array1::zeros(n_obs);
+        let mut mag_weight = Array1::zeros(n_obs);
+        Zip::from(&data)
+            .and(&mut t)
+            .and(&mut mag)
+            .and(&mut mag_weight)
+            .for_each(|obs, t, m, m_w| {
+                *t = obs.t;
+                *m = obs.m;
+                *m_w = obs.err.powi(-2);
+            });
+        (t, mag, mag_weight)
+    };
+
+    TimeSeries::new(time, mag, mag_weight)
+}
+
+/// Time series of the detections left after outlier rejection and indices of the removed
+/// observations
+fn preprocessed_time_series(
+    light_curve: Vec<Observation>,
+    preprocessing: &[OutlierRejection],
+) -> Result<(TimeSeries<'static, f64>, Vec<usize>), BadRequest<String>> {
+    let (detections, removed_indices) = preprocessed_detections(light_curve, preprocessing)?;
+    Ok((data_to_time_series(detections)?, removed_indices))
+}
+
+/// Detections left after outlier rejection and indices of the removed observations
+fn preprocessed_detections(
+    light_curve: Vec<Observation>,
+    preprocessing: &[OutlierRejection],
+) -> Result<(Vec<Observation>, Vec<usize>), BadRequest<String>> {
+    for step in preprocessing {
+        step.validate()?;
+    }
+    let (light_curve, removed_indices) = reject_outliers(light_curve, preprocessing);
+    let detections = light_curve
+        .into_iter()
+        .filter(|obs| !obs.upper_limit)
+        .collect();
+    Ok((detections, removed_indices))
+}
+
+fn flux_ts_from_mag_ts<'a>(mag_ts: &'a TimeSeries<'_, f64>, zp: f64) -> TimeSeries<'a, f64> {
+    let flux = mag_ts.m.sample.mapv(|m| 10_f64.powf(-0.4 * (m - zp)));
+    let flux_weight = {
+        let mut flux_weight = Array1::zeros(mag_ts.lenu());
+        Zip::from(&mut flux_weight)
+            .and(&flux)
+            .and(&mag_ts.w.sample)
+            .for_each(|f_w, &f, &w_m| *f_w = w_m / f64::powi(0.4 * f64::ln(10.0) * f, 2));
+        flux_weight
+    };
+
+    TimeSeries::new(mag_ts.t.sample.view(), flux, flux_weight)
+}
+
+/// Flux-branch extractors for default zero point and curve fit are cached, others are built on
+/// demand
+fn is_cached_flux_fe(zp: f64, curve_fit: &CurveFit) -> bool {
+    zp == MAG_ZP_F64 && *curve_fit == CurveFit::default()
+}
+
+/// Values of the flux features, fit features are refined by the upper `limits` if there are any
+fn eval_flux_features(
+    fe: &FeatureExtractor<f64, Feature<f64>>,
+    flux_ts: &mut TimeSeries<f64>,
+    limits: &[(f64, f64)],
+    zp: f64,
+    curve_fit: &CurveFit,
+) -> Result<Vec<f64>, BadRequest<String>> {
+    if limits.is_empty() {
+        return fe
+            .eval(flux_ts)
+            .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))));
+    }
+    let mut values = vec![];
+    for feature in fe.get_features() {
+        let model = EXTRACTOR_MODELS
+            .iter()
+            .copied()
+            .find(|model| feature.get_names()[0].starts_with(model.name_prefix()));
+        values.extend(match model {
+            Some(model) => model.transformed_values(flux_ts, limits, zp, curve_fit)?,
+            None => feature
+                .eval(flux_ts)
+                .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?,
+        });
+    }
+    Ok(values)
+}
+
+/// Values of the default feature set, the Bazin fit is refined by the upper `limits`
+fn default_features(
+    mag_ts: &mut TimeSeries<f64>,
+    limits: &[(f64, f64)],
+    zp: f64,
+    curve_fit: &CurveFit,
+) -> Result<FeatureValues, BadRequest<String>> {
+    let mag_values = MAG_FE
+        .with(|fe| fe.eval(mag_ts))
+        .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?;
+
+    let mut flux_ts = flux_ts_from_mag_ts(mag_ts, zp);
+    let flux_values = if is_cached_flux_fe(zp, curve_fit) {
+        FLUX_FE.with(|fe| eval_flux_features(fe, &mut flux_ts, limits, zp, curve_fit))
+    } else {
+        let fe = flux_feature_extractor(zp, curve_fit);
+        eval_flux_features(&fe, &mut flux_ts, limits, zp, curve_fit)
+    }?;
+
+    let values = [mag_values, flux_values].concat();
+
+    Ok(FEATURE_NAMES.with(|names| names.iter().cloned().zip(values.into_iter()).collect()))
+}
+
+fn default_extractor_hash(zp: f64, curve_fit: &CurveFit) -> String {
+    if is_cached_flux_fe(zp, curve_fit) {
+        EXTRACTOR_HASH.with(|hash| hash.clone())
+    } else {
+        let flux_fe = flux_feature_extractor(zp, curve_fit);
+        MAG_FE.with(|mag_fe| extractor_hash(&(mag_fe, &flux_fe)))
+    }
+}
+
+/// Values of the transient feature set, the fits are refined by the upper `limits`
+fn transient_features(
+    mag_ts: &TimeSeries<f64>,
+    limits: &[(f64, f64)],
+    zp: f64,
+    curve_fit: &CurveFit,
+) -> Result<FeatureValues, BadRequest<String>> {
+    let mut flux_ts = flux_ts_from_mag_ts(mag_ts, zp);
+    let mut values = if is_cached_flux_fe(zp, curve_fit) {
+        TRANSIENT_FE.with(|fe| eval_flux_features(fe, &mut flux_ts, limits, zp, curve_fit))
+    } else {
+        let fe = transient_feature_extractor(zp, curve_fit);
+        eval_flux_features(&fe, &mut flux_ts, limits, zp, curve_fit)
+    }?;
+    for model in IN_HOUSE_MODELS {
+        values.extend(model.transformed_values(&mut flux_ts, limits, zp, curve_fit)?);
+    }
+
+    Ok(TRANSIENT_FEATURE_NAMES
+        .with(|names| names.iter().cloned().zip(values.into_iter()).collect()))
+}
+
+fn transient_extractor_hash(zp: f64, curve_fit: &CurveFit) -> String {
+    if is_cached_flux_fe(zp, curve_fit) {
+        TRANSIENT_EXTRACTOR_HASH.with(|hash| hash.clone())
+    } else {
+        extractor_hash(&(transient_feature_extractor(zp, curve_fit), IN_HOUSE_MODELS))
+    }
+}
+
+/// Query options of the default feature set
+#[derive(Debug, FromForm)]
+pub struct IndexOptions {
+    meta: bool,
+    fap: bool,
+    harmonics: bool,
+    limits: bool,
+    #[field(default = true)]
+    cache: bool,
+}
+
+/// Default feature set
+///
+/// With `?fap=true` Baluev false-alarm probabilities of the periodogram peaks are added as
+/// `period_fap_{i}_magn` features, with `?harmonics=true` default harmonic fit features are added,
+/// with `?limits=true` features of the non-detections are added and the Bazin fit is refined with
+/// the non-detections as upper limits. Results are cached if the cache is configured,
+/// `?cache=false` bypasses it
+#[post("/?<options..>", format = "application/json", data = "<data>")]
+pub fn index(
+    data: JsonInput<Data>,
+    options: IndexOptions,
+    state: &State<ResultCache>,
+    log: &RequestLog,
+) -> Result<CachedResponse, BadRequest<String>> {
+    let started = Instant::now();
+    let Data {
+        id,
+        light_curve,
+        zero_point,
+        curve_fit,
+        preprocessing,
+        time_correction,
+    } = data.0;
+    let zp = zero_point.value()?;
+    curve_fit.validate()?;
+    let n_obs_input = light_curve.len();
+    log.object(&id, n_obs_input);
+    let IndexOptions {
+        meta,
+        fap,
+        harmonics,
+        limits,
+        cache,
+    } = options;
+    let limit_values = limits.then(|| upper_limit_features(&light_curve));
+    let fit_limits = if limits {
+        upper_limits(&light_curve)
+    } else {
+        vec![]
+    };
+
+    let (mut mag_ts, removed_indices) = preprocessed_time_series(light_curve, &preprocessing)?;
+    let extractor_hash = default_extractor_hash(zp, &curve_fit);
+    let key = cache_key(
+        "index",
+        &format!("zp={:?} curve_fit={:?}", zp, curve_fit),
+        &format!(
+            "fap={} harmonics={} limits={:?}",
+            fap, harmonics, fit_limits
+        ),
+        &mag_ts,
+    );
+    let (mut features, cache_status) = cache::cached(
+        result_cache(state, cache),
+        key,
+        || -> Result<_, BadRequest<String>> {
+            let mut features = default_features(&mut mag_ts, &fit_limits, zp, &curve_fit)?;
+
+            if fap {
+                let settings = PeriodogramSettings::default();
+                let (freq, power) = settings.periodogram().freq_power(&mut mag_ts);
+                let freq: Vec<f64> = freq.into_iter().collect();
+                let power: Vec<f64> = power.into_iter().collect();
+                let max_freq = freq.last().copied().unwrap_or(f64::NAN);
+                for i in 0..settings.peaks {
+                    let period = features[&format!("period_{}_magn", i)];
+                    let peak_power = fap::peak_power(&freq, &power, period);
+                    let probability =
+                        fap::baluev(peak_power, mag_ts.t.sample.as_slice().unwrap(), max_freq);
+                    features.insert(format!("period_fap_{}_magn", i), probability);
+                }
+            }
+
+            if harmonics {
+                let values = HarmonicFitSettings::default().eval(&mut mag_ts)?;
+                features.extend(
+                    HARMONIC_FIT_NAMES
+                        .iter()
+                        .map(|name| format!("{}_magn", name))
+                        .zip(values),
+                );
+            }
+            Ok(features)
+        },
+    )?;
+
+    if let Some(values) = limit_values {
+        features.extend(
+            UPPER_LIMIT_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .zip(values),
+        );
+    }
+
+    let meta = meta.then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            extractor_hash,
+            n_obs_input,
+            mag_ts.t.sample.as_slice().unwrap(),
+            Some(zp),
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+    });
+    Ok(CachedResponse {
+        inner: Json(Response { id, features, meta }),
+        cache: cache_status,
+    })
+}
+
+/// Query options of the transient feature set
+#[derive(Debug, FromForm)]
+pub struct TransientOptions {
+    meta: bool,
+    limits: bool,
+    #[field(default = true)]
+    cache: bool,
+}
+
+/// Parametric fits of the flux light curve: Bazin and Villar fits of light-curve-feature, Linexp
+/// and power-law fits missed in its v0.5 are fitted in-house
+///
+/// Each fit reports its reduced chi-squared as the goodness of fit, with `?limits=true` features of
+/// the non-detections are added and the fits are refined with the non-detections as upper limits.
+/// Results are cached if the cache is configured, `?cache=false` bypasses it
+#[post("/transient?<options..>", format = "application/json", data = "<data>")]
+pub fn transient(
+    data: JsonInput<Data>,
+    options: TransientOptions,
+    state: &State<ResultCache>,
+    log: &RequestLog,
+) -> Result<CachedResponse, BadRequest<String>> {
+    let started = Instant::now();
+    let Data {
+        id,
+        light_curve,
+        zero_point,
+        curve_fit,
+        preprocessing,
+        time_correction,
+    } = data.0;
+    let zp = zero_point.value()?;
+    curve_fit.validate()?;
+    let n_obs_input = light_curve.len();
+    log.object(&id, n_obs_input);
+    let TransientOptions {
+        meta,
+        limits,
+        cache,
+    } = options;
+    let limit_values = limits.then(|| upper_limit_features(&light_curve));
+    let fit_limits = if limits {
+        upper_limits(&light_curve)
+    } else {
+        vec![]
+    };
+
+    let (mag_ts, removed_indices) = preprocessed_time_series(light_curve, &preprocessing)?;
+    let extractor_hash = transient_extractor_hash(zp, &curve_fit);
+    let key = cache_key(
+        "transient",
+        &format!("zp={:?} curve_fit={:?}", zp, curve_fit),
+        &format!("limits={:?}", fit_limits),
+        &mag_ts,
+    );
+    let (mut features, cache_status) = cache::cached(result_cache(state, cache), key, || {
+        transient_features(&mag_ts, &fit_limits, zp, &curve_fit)
+    })?;
+    if let Some(values) = limit_values {
+        features.extend(
+            UPPER_LIMIT_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .zip(values),
+        );
+    }
+
+    let meta = meta.then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            extractor_hash,
+            n_obs_input,
+            mag_ts.t.sample.as_slice().unwrap(),
+            Some(zp),
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+    });
+    Ok(CachedResponse {
+        inner: Json(Response { id, features, meta }),
+        cache: cache_status,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawDataAndFeatures")]
+pub struct DataAndFeatures {
+    id: Option<ObjectId>,
+    light_curve: Vec<Observation>,
+    extractor: Feature<f64>,
+    harmonic_fit: Option<HarmonicFitSettings>,
+    preprocessing: Vec<OutlierRejection>,
+    time_correction: Option<TimeCorrectionRange>,
+}
+
+#[derive(Deserialize)]
+struct RawDataAndFeatures {
+    /// Optional object identifier echoed in the response
+    #[serde(default)]
+    id: Option<ObjectId>,
+    light_curve: Vec<RawObservation>,
+    #[serde(default)]
+    time: TimeSettings,
+    /// Optional light-travel time correction of absolute times
+    #[serde(default)]
+    time_correction: Option<TimeCorrection>,
+    /// Optional Galactic extinction correction of magnitudes
+    #[serde(default)]
+    extinction: Option<Extinction>,
+    extractor: Feature<f64>,
+    /// Optional harmonic fit features evaluated in addition to the extractor
+    #[serde(default)]
+    harmonic_fit: Option<HarmonicFitSettings>,
+    /// Outlier rejection steps applied in order before the evaluation
+    #[serde(default)]
+    preprocessing: Vec<OutlierRejection>,
+}
+
+impl TryFrom<RawDataAndFeatures> for DataAndFeatures {
+    type Error = String;
+
+    fn try_from(raw: RawDataAndFeatures) -> Result<Self, Self::Error> {
+        let (mut light_curve, time_correction) =
+            light_curve_in_days(raw.light_curve, raw.time, raw.time_correction)?;
+        if let Some(extinction) = &raw.extinction {
+            extinction.apply(&mut light_curve)?;
+        }
+        Ok(Self {
+            id: raw.id,
+            light_curve,
+            extractor: raw.extractor,
+            harmonic_fit: raw.harmonic_fit,
+            preprocessing: raw.preprocessing,
+            time_correction,
+        })
+    }
+}
+
+/// Query options of the custom feature set
+#[derive(Debug, FromForm)]
+pub struct FeaturesOptions {
+    meta: bool,
+    #[field(default = true)]
+    cache: bool,
+}
+
+/// Custom feature set given by the `extractor` request field
+///
+/// Results are cached if the cache is configured, `?cache=false` bypasses it
+#[post(
+    "/features?<options..>",
+    format = "application/json",
+    data = "<data_and_features>"
+)]
+pub fn features(
+    data_and_features: JsonInput<DataAndFeatures>,
+    options: FeaturesOptions,
+    state: &State<ResultCache>,
+    log: &RequestLog,
+) -> Result<CachedResponse, BadRequest<String>> {
+    let started = Instant::now();
+    let DataAndFeatures {
+        id,
+        light_curve: data,
+        extractor,
+        harmonic_fit,
+        preprocessing,
+        time_correction,
+    } = data_and_features.0;
+    if let Some(harmonic_fit) = &harmonic_fit {
+        harmonic_fit.validate()?;
+    }
+    let n_obs_input = data.len();
+    log.object(&id, n_obs_input);
+
+    let (mut ts, removed_indices) = preprocessed_time_series(data, &preprocessing)?;
+    let definition = serde_json::to_string(&(&extractor, &harmonic_fit))
+        .expect("extractor must be serializable");
+    let key = cache_key("features", &definition, "", &ts);
+    let (features, cache_status) = cache::cached(
+        result_cache(state, options.cache),
+        key,
+        || -> Result<_, BadRequest<String>> {
+            let values = extractor
+                .eval(&mut ts)
+                .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?;
+
+            let mut features: FeatureValues = extractor
+                .get_names()
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .zip(values.into_iter())
+                .collect();
+            if let Some(harmonic_fit) = &harmonic_fit {
+                let values = harmonic_fit.eval(&mut ts)?;
+                features.extend(
+                    HARMONIC_FIT_NAMES
+                        .iter()
+                        .map(|name| name.to_string())
+                        .zip(values),
+                );
+            }
+            Ok(features)
+        },
+    )?;
+
+    let meta = options.meta.then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            hex_digest(definition.as_bytes()),
+            n_obs_input,
+            ts.t.sample.as_slice().unwrap(),
+            None,
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+    });
+    Ok(CachedResponse {
+        inner: Json(Response { id, features, meta }),
+        cache: cache_status,
+    })
+}
+
+pub struct Version;
+
+impl ApiVersion for Version {
+    fn name(&self) -> &'static str {
+        "v0.5"
+    }
+
+    fn full_version(&self) -> &'static str {
+        LIGHT_CURVE_FEATURE_VERSION
+    }
+
+    fn description(&self) -> &'static str {
+        "Magnitude and flux features of light-curve-feature v0.5 with parametric fits, periodograms, folded, windowed and custom feature sets"
+    }
+
+    fn routes(&self) -> Vec<rocket::Route> {
+        routes![
+            index,
+            transient,
+            fit::fit,
+            periodogram::periodogram,
+            multiband::multiband,
+            folded::folded,
+            windows::windows,
+            features
+        ]
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        FEATURE_NAMES.with(|names| names.clone())
+    }
+
+    fn backends(&self) -> &'static [&'static str] {
+        &["ceres-system", "fftw-system", "gsl"]
+    }
+
+    fn eval(&self, data: Value) -> Result<HashMap<String, f64>, String> {
+        let data: Data = serde_json::from_value(data).map_err(|e| e.to_string())?;
+        let options = IndexOptions {
+            meta: false,
+            fap: false,
+            harmonics: false,
+            limits: false,
+            cache: false,
+        };
+        index(
+            JsonInput(data),
+            options,
+            State::from(&ResultCache::Disabled),
+            &RequestLog::default(),
+        )
+        .map(|response| response.inner.0.features)
+        .map_err(|BadRequest(e)| e.unwrap_or_default())
+    }
+}
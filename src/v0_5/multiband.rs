@@ -0,0 +1,292 @@
+use light_curve_feature_0_5::TimeSeries;
+use rocket::response::status::BadRequest;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::time::Instant;
+
+use super::periodogram::PeriodogramSettings;
+use super::{
+    extractor_hash, observations_to_time_series, preprocessed_detections, Data,
+    LIGHT_CURVE_FEATURE_VERSION, MIN_N_OBS,
+};
+use crate::access_log::{ObjectId, RequestLog};
+use crate::json_input::JsonInput;
+use crate::meta::Meta;
+
+/// Minimum number of detections of a passband, one more than the number of parameters of its
+/// sinusoidal fit, so its χ² is not identically zero
+const MIN_BAND_N_OBS: usize = 4;
+
+/// Weighted sinusoidal fit `offset + a cos(ωt) + b sin(ωt)` of a single passband
+struct HarmonicFit {
+    offset: f64,
+    cos: f64,
+    sin: f64,
+    /// χ² of the fit
+    chi2: f64,
+    /// χ² of the constant weighted-mean model
+    chi2_const: f64,
+}
+
+impl HarmonicFit {
+    /// Solve the normal equations, `t` should be relative to a common reference time
+    fn new(omega: f64, ts: &TimeSeries<f64>, t0: f64) -> Option<Self> {
+        // Weighted sums of the design matrix and right-hand side products
+        let mut sums = [0.0_f64; 10];
+        for ((&t, &y), &w) in
+            ts.t.sample
+                .iter()
+                .zip(ts.m.sample.iter())
+                .zip(ts.w.sample.iter())
+        {
+            let (s, c) = f64::sin_cos(omega * (t - t0));
+            for (sum, x) in
+                sums.iter_mut()
+                    .zip([1.0, c, s, c * c, s * s, c * s, y, y * c, y * s, y * y])
+            {
+                *sum += w * x;
+            }
+        }
+        let [w, c, s, cc, ss, cs, y, yc, ys, yy] = sums;
+
+        let matrix = [[w, c, s], [c, cc, cs], [s, cs, ss]];
+        let rhs = [y, yc, ys];
+        let [offset, cos, sin] = solve3(matrix, rhs)?;
+        let chi2_const = yy - y * y / w;
+        let chi2 = yy - (offset * y + cos * yc + sin * ys);
+        Some(Self {
+            offset,
+            cos,
+            sin,
+            chi2,
+            chi2_const,
+        })
+    }
+
+    fn amplitude(&self) -> f64 {
+        f64::hypot(self.cos, self.sin)
+    }
+
+    /// Phase `φ` of the `A cos(ωt - φ)` representation
+    fn phase(&self) -> f64 {
+        f64::atan2(self.sin, self.cos)
+    }
+}
+
+fn det3(m: [[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// Solve 3x3 linear system with Cramer's rule, `None` for degenerate systems
+fn solve3(matrix: [[f64; 3]; 3], rhs: [f64; 3]) -> Option<[f64; 3]> {
+    let det = det3(matrix);
+    if !det.is_normal() {
+        return None;
+    }
+    let mut solution = [0.0; 3];
+    for (col, x) in solution.iter_mut().enumerate() {
+        let mut m = matrix;
+        for (row, &b) in m.iter_mut().zip(rhs.iter()) {
+            row[col] = b;
+        }
+        *x = det3(m) / det;
+    }
+    Some(solution)
+}
+
+/// Angular frequency grid `step, 2 step, ...` up to the maximum frequency
+fn freq_grid(settings: &PeriodogramSettings, t: &[f64]) -> Result<Vec<f64>, BadRequest<String>> {
+    let (step, size) = settings.freq_grid(t)?;
+    Ok((1..=size).map(|i| step * i as f64).collect())
+}
+
+/// Indices of the highest local maxima in descending order
+fn peak_indices(power: &[f64], n: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..power.len())
+        .filter(|&i| {
+            let left = i.checked_sub(1).map_or(f64::NEG_INFINITY, |j| power[j]);
+            let right = power.get(i + 1).copied().unwrap_or(f64::NEG_INFINITY);
+            power[i] >= left && power[i] > right
+        })
+        .collect();
+    indices.sort_unstable_by(|&a, &b| power[b].total_cmp(&power[a]));
+    indices.truncate(n);
+    indices
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MultibandData {
+    #[serde(flatten)]
+    data: Data,
+    /// The power algorithm is ignored, power is always computed directly
+    #[serde(default)]
+    periodogram: PeriodogramSettings,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BandHarmonic {
+    offset: f64,
+    amplitude: f64,
+    phase: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MultibandPeak {
+    period: f64,
+    power: f64,
+    period_s_to_n: f64,
+    bands: BTreeMap<String, BandHarmonic>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MultibandResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    peaks: Vec<MultibandPeak>,
+    /// Passbands with too few detections to fit, they don't contribute to the periodogram
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dropped_bands: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
+}
+
+/// Multi-band generalized Lomb–Scargle periodogram
+///
+/// Every passband has its own offset and sinusoid amplitude and phase, while the period is shared.
+/// Power is the fraction of the total χ² of the constant models explained by the sinusoids.
+/// Passbands with less than four detections are dropped, the light curve of the remaining ones
+/// must have at least five detections.
+#[post(
+    "/periodogram/multiband?<meta>",
+    format = "application/json",
+    data = "<multiband_data>"
+)]
+pub fn multiband(
+    multiband_data: JsonInput<MultibandData>,
+    meta: Option<bool>,
+    log: &RequestLog,
+) -> Result<Json<MultibandResponse>, BadRequest<String>> {
+    let started = Instant::now();
+    let MultibandData {
+        data,
+        periodogram: settings,
+    } = multiband_data.0;
+    settings.validate()?;
+    let n_obs_input = data.light_curve.len();
+
+    let Data {
+        id,
+        light_curve,
+        preprocessing,
+        time_correction,
+        ..
+    } = data;
+    log.object(&id, n_obs_input);
+
+    // Observations and their indices in the request, for every band
+    let mut light_curves = BTreeMap::new();
+    for (i, obs) in light_curve.into_iter().enumerate() {
+        let band = obs.band.clone().ok_or_else(|| {
+            BadRequest(Some(
+                "Bad request: all observations must have a band for multi-band periodogram".into(),
+            ))
+        })?;
+        let (indices, light_curve) = light_curves.entry(band).or_insert_with(|| (vec![], vec![]));
+        indices.push(i);
+        light_curve.push(obs);
+    }
+    // Outliers are rejected in every band independently
+    let mut removed_indices = vec![];
+    let mut dropped_bands = vec![];
+    let mut band_ts = BTreeMap::new();
+    for (band, (indices, light_curve)) in light_curves {
+        let (detections, removed) = preprocessed_detections(light_curve, &preprocessing)?;
+        removed_indices.extend(removed.into_iter().map(|i| indices[i]));
+        if detections.len() < MIN_BAND_N_OBS {
+            dropped_bands.push(band);
+        } else {
+            band_ts.insert(band, observations_to_time_series(detections));
+        }
+    }
+    removed_indices.sort_unstable();
+
+    let mut t: Vec<f64> = band_ts
+        .values()
+        .flat_map(|ts| ts.t.sample.iter().copied())
+        .collect();
+    if t.len() < MIN_N_OBS {
+        return Err(BadRequest(Some(
+            "Bad request: Light curve must have at least five detections in passbands having at least four detections".into(),
+        )));
+    }
+    t.sort_unstable_by(f64::total_cmp);
+    let t0 = t[0];
+
+    let fit_all = |omega: f64| -> Option<BTreeMap<&str, HarmonicFit>> {
+        band_ts
+            .iter()
+            .map(|(band, ts)| Some((band.as_str(), HarmonicFit::new(omega, ts, t0)?)))
+            .collect()
+    };
+    let power_at = |omega: f64| -> f64 {
+        fit_all(omega).map_or(0.0, |fits| {
+            let chi2_const: f64 = fits.values().map(|fit| fit.chi2_const).sum();
+            let chi2: f64 = fits.values().map(|fit| fit.chi2).sum();
+            if chi2_const > 0.0 {
+                (chi2_const - chi2) / chi2_const
+            } else {
+                0.0
+            }
+        })
+    };
+
+    let freq = freq_grid(&settings, &t)?;
+    let power: Vec<f64> = freq.iter().map(|&omega| power_at(omega)).collect();
+
+    let mean = power.iter().sum::<f64>() / power.len() as f64;
+    let std = f64::sqrt(power.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / power.len() as f64);
+    let peaks = peak_indices(&power, settings.peaks)
+        .into_iter()
+        .filter_map(|i| {
+            let bands = fit_all(freq[i])?
+                .into_iter()
+                .map(|(band, fit)| {
+                    let harmonic = BandHarmonic {
+                        offset: fit.offset,
+                        amplitude: fit.amplitude(),
+                        phase: fit.phase(),
+                    };
+                    (band.to_owned(), harmonic)
+                })
+                .collect();
+            Some(MultibandPeak {
+                period: 2.0 * PI / freq[i],
+                power: power[i],
+                period_s_to_n: (power[i] - mean) / std,
+                bands,
+            })
+        })
+        .collect();
+
+    let meta = meta.unwrap_or(false).then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            extractor_hash(&settings),
+            n_obs_input,
+            &t,
+            None,
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+    });
+    Ok(Json(MultibandResponse {
+        id,
+        peaks,
+        dropped_bands,
+        meta,
+    }))
+}
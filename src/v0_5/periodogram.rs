@@ -0,0 +1,317 @@
+use light_curve_feature_0_5::*;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rocket::response::status::BadRequest;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::time::Instant;
+
+use super::fap::{self, FapMethod};
+use super::{extractor_hash, preprocessed_time_series, Data, LIGHT_CURVE_FEATURE_VERSION};
+use crate::access_log::{ObjectId, RequestLog};
+use crate::json_input::JsonInput;
+use crate::meta::Meta;
+
+/// Maximum size of the frequency grid
+const MAX_FREQ_GRID_SIZE: usize = 100_000;
+
+/// Nyquist frequency estimation strategy, e.g. `"median"` or `{"fixed": 24.0}`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Nyquist {
+    Average,
+    Median,
+    Quantile(f64),
+    Fixed(f64),
+}
+
+impl Nyquist {
+    fn nyquist_freq(self) -> NyquistFreq {
+        match self {
+            Self::Average => NyquistFreq::average(),
+            Self::Median => NyquistFreq::median(),
+            Self::Quantile(q) => NyquistFreq::quantile(q as f32),
+            Self::Fixed(freq) => NyquistFreq::fixed(freq as f32),
+        }
+    }
+
+    /// Angular Nyquist frequency of sorted time moments, same definitions as in
+    /// `light-curve-feature`
+    fn angular_freq(self, t: &[f64]) -> f64 {
+        let mut dt: Vec<f64> = t.windows(2).map(|w| w[1] - w[0]).collect();
+        dt.sort_unstable_by(f64::total_cmp);
+        match self {
+            Self::Average => PI * dt.len() as f64 / (t[t.len() - 1] - t[0]),
+            Self::Median => PI / ppf(&dt, 0.5),
+            Self::Quantile(q) => PI / ppf(&dt, q as f32),
+            Self::Fixed(freq) => freq as f32 as f64,
+        }
+    }
+}
+
+/// Quantile of a sorted slice, R-5 interpolation as `SortedArray::ppf` of `light-curve-feature`
+fn ppf(sorted: &[f64], q: f32) -> f64 {
+    let h = sorted.len() as f32 * q - 0.5;
+    let h_floor = h.floor();
+    if h_floor < 0.0 {
+        sorted[0]
+    } else if h_floor as usize >= sorted.len() - 1 {
+        sorted[sorted.len() - 1]
+    } else {
+        let i = h_floor as usize;
+        sorted[i] + (h - h_floor) as f64 * (sorted[i + 1] - sorted[i])
+    }
+}
+
+/// Periodogram power computation algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerAlgorithm {
+    Fft,
+    Direct,
+}
+
+/// Periodogram parameters, defaults are the same as for the default feature set
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PeriodogramSettings {
+    pub peaks: usize,
+    pub nyquist: Nyquist,
+    pub freq_resolution: f64,
+    pub max_freq_factor: f64,
+    pub algorithm: PowerAlgorithm,
+}
+
+impl Default for PeriodogramSettings {
+    fn default() -> Self {
+        Self {
+            peaks: 5,
+            nyquist: Nyquist::Median,
+            freq_resolution: 10.0,
+            max_freq_factor: 2.0,
+            algorithm: PowerAlgorithm::Fft,
+        }
+    }
+}
+
+impl PeriodogramSettings {
+    pub fn validate(&self) -> Result<(), BadRequest<String>> {
+        let positive = |x: f64| x.is_finite() && x > 0.0;
+        let nyquist_valid = match self.nyquist {
+            Nyquist::Average | Nyquist::Median => true,
+            Nyquist::Quantile(q) => q > 0.0 && q < 1.0,
+            Nyquist::Fixed(freq) => positive(freq),
+        };
+        if self.peaks == 0 {
+            return Err(BadRequest(Some(
+                "Bad request: number of periodogram peaks must be positive".into(),
+            )));
+        }
+        if !nyquist_valid || !positive(self.freq_resolution) || !positive(self.max_freq_factor) {
+            return Err(BadRequest(Some(
+                "Bad request: Nyquist frequency parameter, frequency resolution and maximum frequency factor must be positive, Nyquist quantile must be between 0 and 1".into(),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Step and size of the angular frequency grid `step, 2 step, ...` up to the maximum frequency,
+    /// the same as `FreqGrid::from_t` of `light-curve-feature`. `t` must be sorted and have at
+    /// least two points
+    ///
+    /// The FFT power algorithm extends the grid to the next power of two, the size limit is checked
+    /// for the extended grid
+    pub fn freq_grid(&self, t: &[f64]) -> Result<(f64, usize), BadRequest<String>> {
+        let n = t.len() as f64;
+        let duration = t[t.len() - 1] - t[0];
+        let step = 2.0 * PI * (n - 1.0) / (n * self.freq_resolution as f32 as f64 * duration);
+        let max_freq = self.max_freq_factor as f32 as f64 * self.nyquist.angular_freq(t);
+        let size = (max_freq / step).round();
+        let fits = (1.0..=MAX_FREQ_GRID_SIZE as f64).contains(&size)
+            && match self.algorithm {
+                PowerAlgorithm::Fft => (size as usize).next_power_of_two() <= MAX_FREQ_GRID_SIZE,
+                PowerAlgorithm::Direct => true,
+            };
+        if !fits {
+            return Err(BadRequest(Some(format!(
+                "Bad request: frequency grid must have from 1 to {} points",
+                MAX_FREQ_GRID_SIZE
+            ))));
+        }
+        Ok((step, size as usize))
+    }
+
+    pub fn periodogram(&self) -> Periodogram<f64, Feature<f64>> {
+        let mut periodogram = Periodogram::new(self.peaks);
+        periodogram.set_nyquist(self.nyquist.nyquist_freq());
+        periodogram.set_freq_resolution(self.freq_resolution as f32);
+        periodogram.set_max_freq_factor(self.max_freq_factor as f32);
+        match self.algorithm {
+            PowerAlgorithm::Fft => {
+                periodogram.set_periodogram_algorithm(PeriodogramPowerFft::new().into())
+            }
+            PowerAlgorithm::Direct => {
+                periodogram.set_periodogram_algorithm(PeriodogramPowerDirect {}.into())
+            }
+        };
+        periodogram
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PeriodogramData {
+    #[serde(flatten)]
+    data: Data,
+    #[serde(default)]
+    periodogram: PeriodogramSettings,
+    /// Downsample the spectrum to at most this number of points
+    #[serde(default)]
+    max_points: Option<usize>,
+    /// False-alarm probability estimation method for the peaks
+    #[serde(default)]
+    fap: Option<FapMethod>,
+    /// Random seed of the bootstrap resampling, derived from the light curve by default
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Peak {
+    period: f64,
+    period_s_to_n: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fap: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeriodogramResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    freq: Vec<f64>,
+    power: Vec<f64>,
+    peaks: Vec<Peak>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
+}
+
+/// Keep the highest-power point of each of `max_points` equal-size chunks, so peaks survive
+fn downsample(freq: Vec<f64>, power: Vec<f64>, max_points: usize) -> (Vec<f64>, Vec<f64>) {
+    if max_points == 0 || freq.len() <= max_points {
+        return (freq, power);
+    }
+    let chunk_size = (freq.len() + max_points - 1) / max_points;
+    freq.chunks(chunk_size)
+        .zip(power.chunks(chunk_size))
+        .map(|(freq, power)| {
+            let (i, &power) = power
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap();
+            (freq[i], power)
+        })
+        .unzip()
+}
+
+/// Periodogram power spectrum and its highest peaks
+#[post(
+    "/periodogram?<meta>",
+    format = "application/json",
+    data = "<periodogram_data>"
+)]
+pub fn periodogram(
+    periodogram_data: JsonInput<PeriodogramData>,
+    meta: Option<bool>,
+    log: &RequestLog,
+) -> Result<Json<PeriodogramResponse>, BadRequest<String>> {
+    let started = Instant::now();
+    let PeriodogramData {
+        data,
+        periodogram: settings,
+        max_points,
+        fap: fap_method,
+        seed,
+    } = periodogram_data.0;
+    settings.validate()?;
+    if let Some(fap_method) = &fap_method {
+        fap_method.validate()?;
+    }
+    let n_obs_input = data.light_curve.len();
+    log.object(&data.id, n_obs_input);
+
+    let (mut ts, removed_indices) =
+        preprocessed_time_series(data.light_curve, &data.preprocessing)?;
+    let (id, time_correction) = (data.id, data.time_correction);
+    if ts.lenu() > 1 {
+        settings.freq_grid(ts.t.sample.as_slice().unwrap())?;
+    }
+
+    let periodogram = settings.periodogram();
+    let (freq, power) = periodogram.freq_power(&mut ts);
+    let freq: Vec<f64> = freq.into_iter().collect();
+    let power: Vec<f64> = power.into_iter().collect();
+
+    let peak_values = periodogram
+        .eval(&mut ts)
+        .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?;
+
+    let (bootstrap_max_powers, seed) = match fap_method {
+        Some(FapMethod::Bootstrap { niterations }) => {
+            let seed = seed.unwrap_or_else(|| fap::light_curve_seed(&ts));
+            let max_powers = fap::bootstrap_max_powers(
+                &ts,
+                &periodogram,
+                niterations,
+                &mut Xoshiro256PlusPlus::seed_from_u64(seed),
+            );
+            (max_powers, Some(seed))
+        }
+        _ => (vec![], None),
+    };
+    let peak_fap = |period: f64| {
+        let peak_power = fap::peak_power(&freq, &power, period);
+        fap_method.map(|method| match method {
+            FapMethod::Baluev => fap::baluev(
+                peak_power,
+                ts.t.sample.as_slice().unwrap(),
+                freq.last().copied().unwrap_or(f64::NAN),
+            ),
+            FapMethod::Bootstrap { .. } => fap::bootstrap(peak_power, &bootstrap_max_powers),
+        })
+    };
+
+    let peaks = peak_values
+        .chunks(2)
+        .map(|peak| Peak {
+            period: peak[0],
+            period_s_to_n: peak[1],
+            fap: peak_fap(peak[0]),
+        })
+        .collect();
+
+    let (freq, power) = match max_points {
+        Some(max_points) => downsample(freq, power, max_points),
+        None => (freq, power),
+    };
+
+    let meta = meta.unwrap_or(false).then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            extractor_hash(&(&periodogram, &fap_method)),
+            n_obs_input,
+            ts.t.sample.as_slice().unwrap(),
+            None,
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+        .with_seed(seed)
+    });
+    Ok(Json(PeriodogramResponse {
+        id,
+        freq,
+        power,
+        peaks,
+        meta,
+    }))
+}
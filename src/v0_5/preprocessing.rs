@@ -0,0 +1,160 @@
+use rocket::response::status::BadRequest;
+use rocket::serde::{Deserialize, Serialize};
+
+use super::Observation;
+
+/// Scale factor converting median absolute deviation to standard deviation for normal distribution
+const MAD_TO_STD: f64 = 1.4826;
+
+/// Outlier rejection step, e.g. `{"method": "sigma_clip", "n_sigma": 3.0, "window": 7}`
+///
+/// Every step is a single pass, repeat a step to clip iteratively
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum OutlierRejection {
+    /// Reject magnitudes deviating from the median by more than `n_sigma` standard deviations,
+    /// with `window` the median is the rolling median of this number of time-adjacent observations
+    SigmaClip {
+        #[serde(default = "OutlierRejection::default_n_sigma")]
+        n_sigma: f64,
+        #[serde(default)]
+        window: Option<usize>,
+    },
+    /// Reject magnitudes deviating from the median by more than `threshold` scaled median absolute
+    /// deviations
+    Mad {
+        #[serde(default = "OutlierRejection::default_threshold")]
+        threshold: f64,
+    },
+    /// Reject observations with error bars exceeding the median error by more than `threshold`
+    /// scaled median absolute deviations of the errors
+    ErrorBar {
+        #[serde(default = "OutlierRejection::default_threshold")]
+        threshold: f64,
+    },
+}
+
+impl OutlierRejection {
+    fn default_n_sigma() -> f64 {
+        3.0
+    }
+
+    fn default_threshold() -> f64 {
+        5.0
+    }
+
+    pub fn validate(&self) -> Result<(), BadRequest<String>> {
+        let positive = |x: f64| x.is_finite() && x > 0.0;
+        let valid = match *self {
+            Self::SigmaClip { n_sigma, window } => {
+                positive(n_sigma) && window.map_or(true, |window| window >= 3)
+            }
+            Self::Mad { threshold } | Self::ErrorBar { threshold } => positive(threshold),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(BadRequest(Some(
+                "Bad request: outlier rejection thresholds must be positive, rolling median window must be at least 3".into(),
+            )))
+        }
+    }
+
+    /// Outlier flags of time-sorted observations
+    fn outliers(&self, m: &[f64], err: &[f64]) -> Vec<bool> {
+        match *self {
+            Self::SigmaClip { n_sigma, window } => {
+                let center = match window {
+                    Some(window) => rolling_median(m, window),
+                    None => vec![median(m.to_vec()); m.len()],
+                };
+                let residuals: Vec<f64> = m.iter().zip(center).map(|(m, c)| m - c).collect();
+                let n = residuals.len() as f64;
+                let mean = residuals.iter().sum::<f64>() / n;
+                let std = f64::sqrt(residuals.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n);
+                residuals
+                    .iter()
+                    .map(|r| (r - mean).abs() > n_sigma * std)
+                    .collect()
+            }
+            Self::Mad { threshold } => mad_outliers(m, threshold, false),
+            Self::ErrorBar { threshold } => mad_outliers(err, threshold, true),
+        }
+    }
+}
+
+fn median(mut x: Vec<f64>) -> f64 {
+    x.sort_unstable_by(f64::total_cmp);
+    let n = x.len();
+    if n % 2 == 1 {
+        x[n / 2]
+    } else {
+        0.5 * (x[n / 2 - 1] + x[n / 2])
+    }
+}
+
+/// Median of `window` adjacent values centered on each value, the window is shifted at the edges
+fn rolling_median(x: &[f64], window: usize) -> Vec<f64> {
+    let window = window.min(x.len());
+    (0..x.len())
+        .map(|i| {
+            let begin = i.saturating_sub(window / 2).min(x.len() - window);
+            median(x[begin..begin + window].to_vec())
+        })
+        .collect()
+}
+
+/// Values deviating from the median by more than `threshold` scaled MADs, upper deviations only
+/// if `upper_only`
+fn mad_outliers(x: &[f64], threshold: f64, upper_only: bool) -> Vec<bool> {
+    let center = median(x.to_vec());
+    let mad = median(x.iter().map(|x| (x - center).abs()).collect());
+    x.iter()
+        .map(|&x| {
+            let deviation = if upper_only {
+                x - center
+            } else {
+                (x - center).abs()
+            };
+            deviation > threshold * MAD_TO_STD * mad
+        })
+        .collect()
+}
+
+/// Apply rejection steps in order, returns kept observations sorted by time and ascending indices
+/// of the removed ones in the input light curve
+///
+/// Upper limits are neither used nor removed
+pub fn reject_outliers(
+    light_curve: Vec<Observation>,
+    steps: &[OutlierRejection],
+) -> (Vec<Observation>, Vec<usize>) {
+    if steps.is_empty() {
+        return (light_curve, vec![]);
+    }
+
+    let (mut kept, upper_limits): (Vec<(usize, Observation)>, Vec<_>) = light_curve
+        .into_iter()
+        .enumerate()
+        .partition(|(_, obs)| !obs.upper_limit);
+    kept.sort_unstable_by(|(_, a), (_, b)| a.t.total_cmp(&b.t));
+    let mut removed = vec![];
+    for step in steps {
+        if kept.is_empty() {
+            break;
+        }
+        let m: Vec<f64> = kept.iter().map(|(_, obs)| obs.m).collect();
+        let err: Vec<f64> = kept.iter().map(|(_, obs)| obs.err).collect();
+        let (outliers, inliers): (Vec<_>, Vec<_>) = kept
+            .into_iter()
+            .zip(step.outliers(&m, &err))
+            .partition(|(_, is_outlier)| *is_outlier);
+        removed.extend(outliers.into_iter().map(|((i, _), _)| i));
+        kept = inliers.into_iter().map(|(obs, _)| obs).collect();
+    }
+    removed.sort_unstable();
+
+    kept.extend(upper_limits);
+    kept.sort_by(|(_, a), (_, b)| a.t.total_cmp(&b.t));
+    (kept.into_iter().map(|(_, obs)| obs).collect(), removed)
+}
@@ -0,0 +1,95 @@
+use rocket::serde::{Deserialize, Serialize};
+use time::format_description::well_known::Iso8601;
+use time::{Date, OffsetDateTime, PrimitiveDateTime};
+
+/// Modified Julian date of the Unix epoch
+const UNIX_EPOCH_MJD: f64 = 40587.0;
+
+/// Modified Julian date of the zero Julian date
+const JD_TO_MJD: f64 = -2400000.5;
+
+/// Unit of numeric times of the `number` format
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    #[default]
+    Days,
+    Hours,
+    Seconds,
+}
+
+impl TimeUnit {
+    fn days(self) -> f64 {
+        match self {
+            Self::Days => 1.0,
+            Self::Hours => 1.0 / 24.0,
+            Self::Seconds => 1.0 / 86400.0,
+        }
+    }
+}
+
+/// Format of observation times
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    /// Number with an arbitrary origin in the units of `unit`
+    #[default]
+    Number,
+    /// Modified Julian date
+    Mjd,
+    /// Julian date
+    Jd,
+    /// Unix time in seconds
+    Unix,
+    /// ISO-8601 string, UTC if no offset is given
+    Iso,
+}
+
+/// Time of an observation as given in the request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TimeValue {
+    Number(f64),
+    String(String),
+}
+
+/// Time unit and format of the request, times are converted to days before the evaluation, so
+/// periods and time-scales are always in days
+///
+/// MJD, JD, Unix and ISO-8601 times are converted to MJD
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TimeSettings {
+    pub unit: TimeUnit,
+    pub format: TimeFormat,
+}
+
+impl TimeSettings {
+    /// Time in days
+    pub fn days(&self, t: &TimeValue) -> Result<f64, String> {
+        match (self.format, t) {
+            (TimeFormat::Iso, TimeValue::String(s)) => iso_to_mjd(s),
+            (TimeFormat::Iso, TimeValue::Number(_)) => {
+                Err("times of \"iso\" format must be strings".into())
+            }
+            (_, TimeValue::String(_)) => Err(format!(
+                "times must be numbers for {:?} time format, use \"iso\" format for strings",
+                self.format
+            )),
+            (TimeFormat::Number, &TimeValue::Number(t)) => Ok(t * self.unit.days()),
+            (TimeFormat::Mjd, &TimeValue::Number(t)) => Ok(t),
+            (TimeFormat::Jd, &TimeValue::Number(t)) => Ok(t + JD_TO_MJD),
+            (TimeFormat::Unix, &TimeValue::Number(t)) => Ok(t / 86400.0 + UNIX_EPOCH_MJD),
+        }
+    }
+}
+
+/// Modified Julian date of an ISO-8601 date or date-time
+fn iso_to_mjd(s: &str) -> Result<f64, String> {
+    let datetime = OffsetDateTime::parse(s, &Iso8601::DEFAULT)
+        .or_else(|_| PrimitiveDateTime::parse(s, &Iso8601::DEFAULT).map(|dt| dt.assume_utc()))
+        .or_else(|_| Date::parse(s, &Iso8601::DEFAULT).map(|date| date.midnight().assume_utc()))
+        .map_err(|e| format!("invalid ISO-8601 time {:?}: {}", s, e))?;
+    let unix = datetime.unix_timestamp_nanos() as f64 * 1e-9;
+    Ok(unix / 86400.0 + UNIX_EPOCH_MJD)
+}
@@ -0,0 +1,220 @@
+use light_curve_feature_0_5::ndarray::s;
+use light_curve_feature_0_5::*;
+use rocket::response::status::BadRequest;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use std::time::Instant;
+
+use super::{
+    default_extractor_hash, default_features, extractor_hash, preprocessed_time_series,
+    transient_extractor_hash, transient_features, CurveFit, Data, FeatureValues,
+    LIGHT_CURVE_FEATURE_VERSION,
+};
+use crate::access_log::{ObjectId, RequestLog};
+use crate::json_input::JsonInput;
+use crate::meta::Meta;
+
+/// Maximum number of windows
+const MAX_NWINDOWS: usize = 1_000;
+
+/// Maximum total number of observations in the evaluated windows, bounds the work of overlapping
+/// windows
+const MAX_WINDOW_OBS: usize = 100_000;
+
+/// Time windows `[t_min + i stride, t_min + i stride + size)` covering the light curve
+///
+/// Use `stride` equal to `size` for fixed non-overlapping bins
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WindowSettings {
+    size: f64,
+    stride: f64,
+    /// Windows with fewer observations are skipped
+    #[serde(default = "WindowSettings::default_min_obs")]
+    min_obs: usize,
+}
+
+impl WindowSettings {
+    fn default_min_obs() -> usize {
+        5
+    }
+
+    fn validate(&self) -> Result<(), BadRequest<String>> {
+        let positive = |x: f64| x.is_finite() && x > 0.0;
+        if positive(self.size) && positive(self.stride) {
+            Ok(())
+        } else {
+            Err(BadRequest(Some(
+                "Bad request: window size and stride must be positive".into(),
+            )))
+        }
+    }
+
+    /// Window start times
+    fn starts(&self, t_min: f64, t_max: f64) -> Result<Vec<f64>, BadRequest<String>> {
+        let nwindows = ((t_max - t_min) / self.stride).floor() + 1.0;
+        if nwindows > MAX_NWINDOWS as f64 {
+            return Err(BadRequest(Some(format!(
+                "Bad request: there must be at most {} windows",
+                MAX_NWINDOWS
+            ))));
+        }
+        Ok((0..nwindows as usize)
+            .map(|i| t_min + self.stride * i as f64)
+            .collect())
+    }
+}
+
+/// Extractor evaluated in every window: `"default"`, `"transient"` or `{"custom": {...}}`
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowExtractor {
+    #[default]
+    Default,
+    Transient,
+    Custom(Feature<f64>),
+}
+
+impl WindowExtractor {
+    fn eval(
+        &self,
+        ts: &mut TimeSeries<f64>,
+        zp: f64,
+        curve_fit: &CurveFit,
+    ) -> Result<FeatureValues, BadRequest<String>> {
+        match self {
+            Self::Default => default_features(ts, &[], zp, curve_fit),
+            Self::Transient => transient_features(ts, &[], zp, curve_fit),
+            Self::Custom(extractor) => {
+                let values = extractor
+                    .eval(ts)
+                    .map_err(|e| BadRequest(Some(format!("Bad request: {:?}", e))))?;
+                Ok(extractor
+                    .get_names()
+                    .iter()
+                    .map(|name| name.to_string())
+                    .zip(values)
+                    .collect())
+            }
+        }
+    }
+
+    fn hash(&self, zp: f64, curve_fit: &CurveFit) -> String {
+        match self {
+            Self::Default => default_extractor_hash(zp, curve_fit),
+            Self::Transient => transient_extractor_hash(zp, curve_fit),
+            Self::Custom(extractor) => extractor_hash(extractor),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WindowData {
+    #[serde(flatten)]
+    data: Data,
+    window: WindowSettings,
+    #[serde(default)]
+    extractor: WindowExtractor,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Window {
+    t_start: f64,
+    t_end: f64,
+    n_obs: usize,
+    /// `null` if the evaluation has failed
+    features: Option<FeatureValues>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WindowResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<ObjectId>,
+    windows: Vec<Window>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
+}
+
+/// Features evaluated in sliding or fixed time windows
+#[post("/windows?<meta>", format = "application/json", data = "<window_data>")]
+pub fn windows(
+    window_data: JsonInput<WindowData>,
+    meta: Option<bool>,
+    log: &RequestLog,
+) -> Result<Json<WindowResponse>, BadRequest<String>> {
+    let started = Instant::now();
+    let WindowData {
+        data,
+        window,
+        extractor,
+    } = window_data.0;
+    let Data {
+        id,
+        light_curve,
+        zero_point,
+        curve_fit,
+        preprocessing,
+        time_correction,
+    } = data;
+    let zp = zero_point.value()?;
+    curve_fit.validate()?;
+    window.validate()?;
+    let n_obs_input = light_curve.len();
+    log.object(&id, n_obs_input);
+
+    let (ts, removed_indices) = preprocessed_time_series(light_curve, &preprocessing)?;
+    let t = ts.t.sample.as_slice().unwrap();
+
+    let ranges: Vec<_> = window
+        .starts(t[0], t[t.len() - 1])?
+        .into_iter()
+        .map(|t_start| {
+            let begin = t.partition_point(|&t| t < t_start);
+            let end = t.partition_point(|&t| t < t_start + window.size);
+            (t_start, begin..end)
+        })
+        .filter(|(_, range)| range.len() >= window.min_obs)
+        .collect();
+    if ranges.iter().map(|(_, range)| range.len()).sum::<usize>() > MAX_WINDOW_OBS {
+        return Err(BadRequest(Some(format!(
+            "Bad request: windows must have at most {} observations in total",
+            MAX_WINDOW_OBS
+        ))));
+    }
+
+    let windows = ranges
+        .into_iter()
+        .map(|(t_start, range)| {
+            let mut window_ts = TimeSeries::new(
+                ts.t.sample.slice(s![range.clone()]),
+                ts.m.sample.slice(s![range.clone()]),
+                ts.w.sample.slice(s![range.clone()]),
+            );
+            let (features, error) = match extractor.eval(&mut window_ts, zp, &curve_fit) {
+                Ok(features) => (Some(features), None),
+                Err(BadRequest(error)) => (None, error),
+            };
+            Window {
+                t_start,
+                t_end: t_start + window.size,
+                n_obs: range.len(),
+                features,
+                error,
+            }
+        })
+        .collect();
+
+    let meta = meta.unwrap_or(false).then(|| {
+        Meta::new(
+            LIGHT_CURVE_FEATURE_VERSION,
+            extractor_hash(&(extractor.hash(zp, &curve_fit), &window)),
+            n_obs_input,
+            t,
+            Some(zp),
+            started,
+        )
+        .with_removed_indices(removed_indices)
+        .with_time_correction(time_correction)
+    });
+    Ok(Json(WindowResponse { id, windows, meta }))
+}
@@ -0,0 +1,77 @@
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::serde::Serialize;
+use rocket::{Build, Rocket};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::health::canary_light_curve;
+use crate::registry::{self, ApiVersion};
+
+/// Number of warmed-up threads
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Longest warm-up of a thread, in microseconds
+static MAX_TIME_US: AtomicU64 = AtomicU64::new(0);
+
+/// Warm-up summary
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmUpReport {
+    /// Number of warmed-up runtime threads
+    pub threads: usize,
+    /// Longest warm-up of a thread, in milliseconds
+    pub time_ms: f64,
+}
+
+/// Warm-up summary of the threads started so far, `None` if none has been warmed up
+pub fn report() -> Option<WarmUpReport> {
+    match THREADS.load(Ordering::Relaxed) {
+        0 => None,
+        threads => Some(WarmUpReport {
+            threads,
+            time_ms: MAX_TIME_US.load(Ordering::Relaxed) as f64 * 1e-3,
+        }),
+    }
+}
+
+/// Initialize thread-local extractors and FFTW plans of the current thread
+///
+/// The server runtime calls it on the start of every its thread, so the threads are warmed up
+/// before they poll any request
+pub fn warm_up_thread() {
+    let started = Instant::now();
+    let data = canary_light_curve();
+    for version in registry::VERSIONS.iter() {
+        if let Err(e) = version.eval(data.clone()) {
+            error!("Warm-up of API {} failed: {}", version.name(), e);
+        }
+    }
+    MAX_TIME_US.fetch_max(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+    THREADS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Warms up the igniting thread if no runtime thread has been warmed up, e.g. of the local
+/// client of the tests which builds its runtime without the thread-start hook
+pub struct WarmUp;
+
+#[rocket::async_trait]
+impl Fairing for WarmUp {
+    fn info(&self) -> Info {
+        Info {
+            name: "Warm-up of feature extractors",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        if THREADS.load(Ordering::Relaxed) == 0 {
+            warm_up_thread();
+        }
+        if let Some(report) = report() {
+            info!(
+                "{} threads are warmed up, the longest warm-up took {:.0} ms",
+                report.threads, report.time_ms
+            );
+        }
+        Ok(rocket)
+    }
+}